@@ -0,0 +1,91 @@
+## Validation errors
+
+validation-failed = Validation failed
+validation-name-empty = Name must not be empty
+validation-name-too-long = Name must be at most { $max } characters
+validation-vendor-name-empty = Vendor name must not be empty
+validation-description-too-long = Description must be at most { $max } characters
+validation-price-invalid = Price must be a non-negative number
+validation-price-too-high = Price must not exceed { $max }
+validation-price-precision = { $places ->
+    [0] Prices in { $currency } must be whole amounts
+   *[other] Prices in { $currency } can have at most { $places } decimal places
+}
+validation-image-url-too-long = Image URL is too long
+validation-image-url-not-uploaded = Image URL must point to an uploaded file
+validation-image-url-invalid = Image URL must be an http(s) URL or an uploaded file path
+validation-too-many-tags = A product can have at most { $max } tags
+validation-too-many-categories = A product can be in at most { $max } categories
+validation-duplicate-category = Category { $id } is listed more than once
+validation-duplicate-tag = Tag { $id } is listed more than once
+validation-unknown-category = Unknown category { $id }
+validation-unknown-tag = Unknown tag { $id }
+validation-email-invalid = Vendor email is not valid
+validation-password-required = Password is required
+validation-password-too-short = Password must be at least { $min } characters long
+validation-current-password-required = Enter your current password to change your email or password
+validation-current-password-wrong = Current password is incorrect
+validation-webhook-url-invalid = Webhook URL must be an absolute http(s) URL
+validation-webhook-url-private = Webhook URL must point to a public address
+validation-webhook-url-unresolvable = Webhook URL host could not be resolved
+validation-webhook-secret-too-short = Secret must be at least { $min } characters long
+validation-slug-invalid = Slug must be lowercase letters and digits separated by single dashes
+validation-slug-uuid = Slug must not be formatted like an id
+
+## Verification report
+
+rule-name-present = Product name is provided
+rule-name-missing = Product name is missing
+rule-description-present = Product description is provided
+rule-description-missing = Product description is missing
+rule-image-present = Product image is provided
+rule-image-missing = Product image is missing
+rule-category-assigned = Product has a category
+rule-category-missing = Product must be assigned to a category
+rule-tag-assigned = Product has at least one tag
+rule-tag-missing = Product must have at least one tag
+rule-price-in-range = Price is within the allowed range
+rule-price-out-of-range = Price { $price } must be between { $min } and { $max }
+rule-price-no-range = No price range is configured for { $currency }
+rule-description-length-ok = Description length is acceptable
+rule-description-too-short = Description must be at least { $min } characters (currently { $length })
+rule-description-too-long = Description must be at most { $max } characters (currently { $length })
+rule-image-size-ok = Image is { $width }x{ $height } pixels
+rule-image-too-small = Image is { $width }x{ $height } pixels but must be at least { $min_width }x{ $min_height }
+rule-image-unreadable = Image could not be read: { $error }
+rule-image-not-checked = Image is not an uploaded file; dimensions were not checked
+rule-no-banned-words = No banned words found
+rule-banned-words = Listing contains banned words: { $words }
+
+## Notifications
+## Each kind has the in-app text as its value, plus the email subject and closing line.
+
+-brand = Made in Cameroon
+product-fallback = your product
+email-greeting = Hello { $name },
+email-signature = { -brand }
+
+notification-general =
+    .subject = An update about "{ $product }"
+    .next-step = Open your dashboard for details.
+notification-verification-passed = Your product "{ $product }" passed automatic checks and is awaiting review by our team.
+    .subject = "{ $product }" passed automatic checks
+    .next-step = A moderator will review it shortly; we will let you know once it is live.
+notification-verification-failed = Your product "{ $product }" failed verification.
+    .with-reasons = Your product "{ $product }" failed verification: { $reasons }.
+    .subject = "{ $product }" needs changes before it can be published
+    .next-step = Update the product from your dashboard and submit it again.
+notification-moderation-approved = Your product "{ $product }" has been approved and is now live!
+    .subject = "{ $product }" is now live
+    .next-step = Customers can now find it on the marketplace.
+notification-moderation-rejected = Your product "{ $product }" was rejected by a moderator.
+    .with-reason = Your product "{ $product }" was rejected by a moderator: { $reason }
+    .subject = "{ $product }" was not approved
+    .next-step = Move it back to draft, address the feedback and submit it again.
+notification-product-suspended = Your product "{ $product }" was suspended by a moderator.
+    .with-reason = Your product "{ $product }" was suspended by a moderator: { $reason }
+    .subject = "{ $product }" has been suspended
+    .next-step = It is hidden from customers until a moderator reinstates it.
+notification-product-reinstated = Your product "{ $product }" has been reinstated and is live again.
+    .subject = "{ $product }" is live again
+    .next-step = Customers can find it on the marketplace again.
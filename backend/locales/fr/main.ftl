@@ -0,0 +1,91 @@
+## Erreurs de validation
+
+validation-failed = La validation a échoué
+validation-name-empty = Le nom ne doit pas être vide
+validation-name-too-long = Le nom doit comporter au plus { $max } caractères
+validation-vendor-name-empty = Le nom du vendeur ne doit pas être vide
+validation-description-too-long = La description doit comporter au plus { $max } caractères
+validation-price-invalid = Le prix doit être un nombre positif ou nul
+validation-price-too-high = Le prix ne doit pas dépasser { $max }
+validation-price-precision = { $places ->
+    [0] Les prix en { $currency } doivent être des montants entiers
+   *[other] Les prix en { $currency } ne peuvent avoir plus de { $places } décimales
+}
+validation-image-url-too-long = L'URL de l'image est trop longue
+validation-image-url-not-uploaded = L'URL de l'image doit désigner un fichier téléversé
+validation-image-url-invalid = L'URL de l'image doit être une URL http(s) ou le chemin d'un fichier téléversé
+validation-too-many-tags = Un produit peut avoir au plus { $max } étiquettes
+validation-too-many-categories = Un produit peut appartenir à au plus { $max } catégories
+validation-duplicate-category = La catégorie { $id } apparaît plusieurs fois
+validation-duplicate-tag = L'étiquette { $id } apparaît plusieurs fois
+validation-unknown-category = Catégorie inconnue { $id }
+validation-unknown-tag = Étiquette inconnue { $id }
+validation-email-invalid = L'adresse e-mail du vendeur n'est pas valide
+validation-password-required = Le mot de passe est obligatoire
+validation-password-too-short = Le mot de passe doit comporter au moins { $min } caractères
+validation-current-password-required = Saisissez votre mot de passe actuel pour modifier votre e-mail ou votre mot de passe
+validation-current-password-wrong = Le mot de passe actuel est incorrect
+validation-webhook-url-invalid = L'URL du webhook doit être une URL http(s) absolue
+validation-webhook-url-private = L'URL du webhook doit désigner une adresse publique
+validation-webhook-url-unresolvable = L'hôte de l'URL du webhook est introuvable
+validation-webhook-secret-too-short = Le secret doit comporter au moins { $min } caractères
+validation-slug-invalid = Le slug doit être composé de lettres minuscules et de chiffres séparés par des tirets simples
+validation-slug-uuid = Le slug ne doit pas avoir la forme d'un identifiant
+
+## Rapport de vérification
+
+rule-name-present = Le nom du produit est renseigné
+rule-name-missing = Le nom du produit est manquant
+rule-description-present = La description du produit est renseignée
+rule-description-missing = La description du produit est manquante
+rule-image-present = L'image du produit est fournie
+rule-image-missing = L'image du produit est manquante
+rule-category-assigned = Le produit a une catégorie
+rule-category-missing = Le produit doit être rattaché à une catégorie
+rule-tag-assigned = Le produit a au moins une étiquette
+rule-tag-missing = Le produit doit avoir au moins une étiquette
+rule-price-in-range = Le prix est dans la fourchette autorisée
+rule-price-out-of-range = Le prix { $price } doit être compris entre { $min } et { $max }
+rule-price-no-range = Aucune fourchette de prix n'est configurée pour { $currency }
+rule-description-length-ok = La longueur de la description est acceptable
+rule-description-too-short = La description doit comporter au moins { $min } caractères (actuellement { $length })
+rule-description-too-long = La description doit comporter au plus { $max } caractères (actuellement { $length })
+rule-image-size-ok = L'image mesure { $width }x{ $height } pixels
+rule-image-too-small = L'image mesure { $width }x{ $height } pixels mais doit mesurer au moins { $min_width }x{ $min_height }
+rule-image-unreadable = L'image n'a pas pu être lue : { $error }
+rule-image-not-checked = L'image n'est pas un fichier téléversé ; ses dimensions n'ont pas été vérifiées
+rule-no-banned-words = Aucun mot interdit trouvé
+rule-banned-words = L'annonce contient des mots interdits : { $words }
+
+## Notifications
+## Chaque type a le texte affiché dans l'application, plus l'objet de l'e-mail et sa phrase de conclusion.
+
+-brand = Made in Cameroon
+product-fallback = votre produit
+email-greeting = Bonjour { $name },
+email-signature = { -brand }
+
+notification-general =
+    .subject = Du nouveau concernant « { $product } »
+    .next-step = Consultez votre tableau de bord pour plus de détails.
+notification-verification-passed = Votre produit « { $product } » a passé les contrôles automatiques et attend l'examen de notre équipe.
+    .subject = « { $product } » a passé les contrôles automatiques
+    .next-step = Un modérateur va l'examiner sous peu ; nous vous préviendrons dès qu'il sera en ligne.
+notification-verification-failed = Votre produit « { $product } » n'a pas passé la vérification.
+    .with-reasons = Votre produit « { $product } » n'a pas passé la vérification : { $reasons }.
+    .subject = « { $product } » doit être modifié avant publication
+    .next-step = Modifiez le produit depuis votre tableau de bord et soumettez-le à nouveau.
+notification-moderation-approved = Votre produit « { $product } » a été approuvé et est maintenant en ligne !
+    .subject = « { $product } » est maintenant en ligne
+    .next-step = Les clients peuvent désormais le trouver sur la place de marché.
+notification-moderation-rejected = Votre produit « { $product } » a été refusé par un modérateur.
+    .with-reason = Votre produit « { $product } » a été refusé par un modérateur : { $reason }
+    .subject = « { $product } » n'a pas été approuvé
+    .next-step = Repassez-le en brouillon, tenez compte des remarques et soumettez-le à nouveau.
+notification-product-suspended = Votre produit « { $product } » a été suspendu par un modérateur.
+    .with-reason = Votre produit « { $product } » a été suspendu par un modérateur : { $reason }
+    .subject = « { $product } » a été suspendu
+    .next-step = Il est masqué aux clients jusqu'à ce qu'un modérateur le rétablisse.
+notification-product-reinstated = Votre produit « { $product } » a été rétabli et est de nouveau en ligne.
+    .subject = « { $product } » est de nouveau en ligne
+    .next-step = Les clients peuvent de nouveau le trouver sur la place de marché.
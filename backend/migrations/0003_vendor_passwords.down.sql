@@ -0,0 +1 @@
+ALTER TABLE vendors DROP COLUMN IF EXISTS password_hash;
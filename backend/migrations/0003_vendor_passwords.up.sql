@@ -0,0 +1,2 @@
+-- Vendors created before authentication existed have no password until they set one
+ALTER TABLE vendors ADD COLUMN IF NOT EXISTS password_hash TEXT;
@@ -0,0 +1,11 @@
+-- One row per submission, listing every rule that was checked and why it failed
+CREATE TABLE IF NOT EXISTS verification_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id),
+    passed BOOLEAN NOT NULL,
+    rules JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS verification_reports_product_idx
+ON verification_reports (product_id, created_at DESC);
@@ -0,0 +1,20 @@
+-- Rules with a NULL category apply to every product; a category-specific rule
+-- replaces the global rule of the same kind for products in that category.
+CREATE TABLE IF NOT EXISTS verification_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    category_id UUID REFERENCES categories(id),
+    config JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS verification_rules_category_idx
+ON verification_rules (category_id);
+
+-- The global rules reproduce the checks that used to be hard-coded in verify_product.
+-- Food & Drinks additionally needs a fuller description than other categories.
+INSERT INTO verification_rules (category_id, config)
+VALUES
+    (NULL, '{"kind": "required_fields", "fields": ["name", "description", "image", "category", "tag"]}'),
+    (NULL, '{"kind": "price_range", "min": 500, "max": 1000000000}'),
+    ('22222222-2222-2222-2222-222222222222', '{"kind": "description_length", "min": 80, "max": 5000}');
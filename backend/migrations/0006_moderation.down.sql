@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS moderation_decisions;
+DROP INDEX IF EXISTS products_pending_review_idx;
+ALTER TABLE products DROP COLUMN IF EXISTS moderation_status;
+ALTER TABLE vendors DROP COLUMN IF EXISTS role;
@@ -0,0 +1,18 @@
+-- Staff accounts live alongside vendors and are told apart by role
+ALTER TABLE vendors ADD COLUMN IF NOT EXISTS role VARCHAR(32) NOT NULL DEFAULT 'vendor';
+
+-- NULL until a product passes automatic checks, then pending_review, approved or rejected
+ALTER TABLE products ADD COLUMN IF NOT EXISTS moderation_status VARCHAR(32);
+
+CREATE INDEX IF NOT EXISTS products_pending_review_idx
+ON products (updated_at)
+WHERE moderation_status = 'pending_review';
+
+CREATE TABLE IF NOT EXISTS moderation_decisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id),
+    moderator_id UUID NOT NULL REFERENCES vendors(id),
+    decision VARCHAR(16) NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
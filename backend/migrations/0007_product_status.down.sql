@@ -0,0 +1,41 @@
+CREATE TABLE IF NOT EXISTS moderation_decisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id),
+    moderator_id UUID NOT NULL REFERENCES vendors(id),
+    decision VARCHAR(16) NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO moderation_decisions (product_id, moderator_id, decision, reason, created_at)
+SELECT product_id, actor_id, CASE WHEN to_status = 'live' THEN 'approved' ELSE 'rejected' END, reason, created_at
+FROM product_status_history
+WHERE from_status = 'in_review' AND to_status IN ('live', 'rejected') AND actor_id IS NOT NULL;
+
+DROP TABLE IF EXISTS product_status_history;
+DROP INDEX IF EXISTS products_status_idx;
+
+ALTER TABLE products
+    ADD COLUMN IF NOT EXISTS is_draft BOOLEAN DEFAULT true,
+    ADD COLUMN IF NOT EXISTS is_verified BOOLEAN DEFAULT false,
+    ADD COLUMN IF NOT EXISTS moderation_status VARCHAR(32);
+
+UPDATE products SET
+    is_draft = status IN ('draft', 'archived'),
+    is_verified = CASE
+        WHEN status = 'live' THEN true
+        WHEN status IN ('submitted', 'in_review') THEN NULL
+        ELSE false
+    END,
+    moderation_status = CASE
+        WHEN status = 'in_review' THEN 'pending_review'
+        WHEN status = 'live' THEN 'approved'
+        WHEN status = 'rejected' THEN 'rejected'
+    END;
+
+CREATE INDEX IF NOT EXISTS products_pending_review_idx
+ON products (updated_at)
+WHERE moderation_status = 'pending_review';
+
+ALTER TABLE products DROP COLUMN IF EXISTS status;
+DROP TYPE IF EXISTS product_status;
@@ -0,0 +1,78 @@
+-- Postgres has no CREATE TYPE IF NOT EXISTS
+DO $$
+BEGIN
+    CREATE TYPE product_status AS ENUM (
+        'draft',
+        'submitted',
+        'in_review',
+        'live',
+        'rejected',
+        'archived',
+        'suspended'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+ALTER TABLE products ADD COLUMN IF NOT EXISTS status product_status NOT NULL DEFAULT 'draft';
+
+-- Collapse the old flag combinations into a single state; nothing to do once the flags are gone
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'products' AND column_name = 'moderation_status'
+    ) THEN
+        UPDATE products SET status = CASE
+            WHEN moderation_status = 'pending_review' THEN 'in_review'
+            WHEN moderation_status = 'rejected' THEN 'rejected'
+            WHEN is_draft THEN 'draft'
+            WHEN is_verified IS NULL THEN 'submitted'
+            WHEN is_verified THEN 'live'
+            ELSE 'rejected'
+        END::product_status;
+    END IF;
+END
+$$;
+
+DROP INDEX IF EXISTS products_pending_review_idx;
+ALTER TABLE products
+    DROP COLUMN IF EXISTS is_draft,
+    DROP COLUMN IF EXISTS is_verified,
+    DROP COLUMN IF EXISTS moderation_status;
+
+CREATE INDEX IF NOT EXISTS products_status_idx ON products (status, updated_at);
+
+CREATE TABLE IF NOT EXISTS product_status_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id),
+    from_status product_status NOT NULL,
+    to_status product_status NOT NULL,
+    -- NULL when the transition was made by the system, e.g. automatic verification
+    actor_id UUID REFERENCES vendors(id),
+    reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS product_status_history_product_idx
+ON product_status_history (product_id, created_at);
+
+-- Moderator decisions are now ordinary status transitions
+DO $$
+BEGIN
+    IF to_regclass('moderation_decisions') IS NOT NULL THEN
+        INSERT INTO product_status_history (product_id, from_status, to_status, actor_id, reason, created_at)
+        SELECT
+            product_id,
+            'in_review',
+            (CASE WHEN decision = 'approved' THEN 'live' ELSE 'rejected' END)::product_status,
+            moderator_id,
+            reason,
+            created_at
+        FROM moderation_decisions;
+    END IF;
+END
+$$;
+
+DROP TABLE IF EXISTS moderation_decisions;
@@ -0,0 +1,26 @@
+CREATE TYPE job_status AS ENUM (
+    'queued',
+    'running',
+    'succeeded',
+    'dead'
+);
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    status job_status NOT NULL DEFAULT 'queued',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 5,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_at TIMESTAMPTZ,
+    last_error TEXT,
+    -- Account that caused the job, if any; used to scope who may read it
+    owner_id UUID REFERENCES vendors(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Workers only ever scan runnable jobs
+CREATE INDEX IF NOT EXISTS jobs_runnable_idx ON jobs (run_at) WHERE status IN ('queued', 'running');
+CREATE INDEX IF NOT EXISTS jobs_dead_idx ON jobs (updated_at) WHERE status = 'dead';
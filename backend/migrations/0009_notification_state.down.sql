@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS notifications_unread_idx;
+DROP INDEX IF EXISTS notifications_inbox_idx;
+ALTER TABLE notifications DROP COLUMN IF EXISTS archived_at;
+ALTER TABLE notifications ALTER COLUMN is_read DROP NOT NULL;
@@ -0,0 +1,13 @@
+UPDATE notifications SET is_read = false WHERE is_read IS NULL;
+ALTER TABLE notifications ALTER COLUMN is_read SET NOT NULL;
+
+-- Archived notifications are hidden from the inbox but kept for reference
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS archived_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS notifications_inbox_idx
+    ON notifications (vendor_id, created_at DESC)
+    WHERE archived_at IS NULL;
+-- Keeps the navbar badge count cheap for vendors with thousands of rows
+CREATE INDEX IF NOT EXISTS notifications_unread_idx
+    ON notifications (vendor_id)
+    WHERE NOT is_read AND archived_at IS NULL;
@@ -0,0 +1,15 @@
+-- Wakes up open notification streams; listeners re-read the table, so the payload stays small
+CREATE OR REPLACE FUNCTION notify_notification_created() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify(
+        'notifications',
+        json_build_object('id', NEW.id, 'vendor_id', NEW.vendor_id)::text
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS notifications_created_notify ON notifications;
+CREATE TRIGGER notifications_created_notify
+    AFTER INSERT ON notifications
+    FOR EACH ROW EXECUTE FUNCTION notify_notification_created();
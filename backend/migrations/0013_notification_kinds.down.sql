@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS notifications_kind_idx;
+ALTER TABLE notifications DROP COLUMN IF EXISTS payload, DROP COLUMN IF EXISTS kind;
+DROP TYPE IF EXISTS notification_kind;
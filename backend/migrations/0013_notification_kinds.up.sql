@@ -0,0 +1,26 @@
+CREATE TYPE notification_kind AS ENUM (
+    'general',
+    'verification_passed',
+    'verification_failed',
+    'moderation_approved',
+    'moderation_rejected',
+    'product_suspended',
+    'product_reinstated'
+);
+
+ALTER TABLE notifications
+    ADD COLUMN kind notification_kind NOT NULL DEFAULT 'general',
+    ADD COLUMN payload JSONB NOT NULL DEFAULT '{}';
+
+-- Classify existing rows from the fixed wording they were written with; the rest stay general
+UPDATE notifications SET kind = CASE
+    WHEN message LIKE 'Your product passed automatic checks%' THEN 'verification_passed'
+    WHEN message LIKE 'Your product verification failed%' THEN 'verification_failed'
+    WHEN message LIKE 'Your product has been approved%' THEN 'moderation_approved'
+    WHEN message LIKE 'Your product was rejected by a moderator%' THEN 'moderation_rejected'
+    WHEN message LIKE 'Your product was suspended%' THEN 'product_suspended'
+    WHEN message LIKE 'Your product has been reinstated%' THEN 'product_reinstated'
+    ELSE 'general'
+END::notification_kind;
+
+CREATE INDEX IF NOT EXISTS notifications_kind_idx ON notifications (vendor_id, kind);
@@ -0,0 +1,2 @@
+-- Language for notifications, emails and messages; NULL follows the request's Accept-Language
+ALTER TABLE vendors ADD COLUMN locale VARCHAR(8);
@@ -0,0 +1,16 @@
+ALTER TABLE verification_rules
+    DROP CONSTRAINT IF EXISTS verification_rules_category_id_fkey,
+    ADD CONSTRAINT verification_rules_category_id_fkey
+        FOREIGN KEY (category_id) REFERENCES categories(id);
+
+DROP INDEX IF EXISTS tags_name_lower_idx;
+DROP INDEX IF EXISTS categories_name_lower_idx;
+
+-- Proposals that were never approved did not exist before this migration
+DELETE FROM tags WHERE status = 'pending';
+
+ALTER TABLE tags
+    DROP COLUMN IF EXISTS proposed_by,
+    DROP COLUMN IF EXISTS status;
+
+DROP TYPE IF EXISTS tag_status;
@@ -0,0 +1,21 @@
+-- Vendor-proposed tags wait for a moderator before they can be attached to products
+CREATE TYPE tag_status AS ENUM (
+    'pending',
+    'approved'
+);
+
+ALTER TABLE tags
+    ADD COLUMN status tag_status NOT NULL DEFAULT 'approved',
+    ADD COLUMN proposed_by UUID REFERENCES vendors(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS tags_pending_idx ON tags (created_at) WHERE status = 'pending';
+
+-- Names are compared case-insensitively so "Electronics" and "electronics" cannot coexist
+CREATE UNIQUE INDEX IF NOT EXISTS categories_name_lower_idx ON categories (LOWER(name));
+CREATE UNIQUE INDEX IF NOT EXISTS tags_name_lower_idx ON tags (LOWER(name));
+
+-- Category-specific verification rules go away with their category
+ALTER TABLE verification_rules
+    DROP CONSTRAINT IF EXISTS verification_rules_category_id_fkey,
+    ADD CONSTRAINT verification_rules_category_id_fkey
+        FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE CASCADE;
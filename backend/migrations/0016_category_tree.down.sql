@@ -0,0 +1,12 @@
+-- Fails if two categories share a name, which the tree allows under different parents
+DROP INDEX IF EXISTS categories_parent_idx;
+DROP INDEX IF EXISTS categories_sibling_name_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS categories_name_lower_idx ON categories (LOWER(name));
+ALTER TABLE categories ADD CONSTRAINT categories_name_key UNIQUE (name);
+
+ALTER TABLE categories
+    DROP CONSTRAINT IF EXISTS categories_not_own_parent,
+    DROP CONSTRAINT IF EXISTS categories_slug_key,
+    DROP COLUMN IF EXISTS position,
+    DROP COLUMN IF EXISTS slug,
+    DROP COLUMN IF EXISTS parent_id;
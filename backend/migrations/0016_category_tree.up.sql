@@ -0,0 +1,28 @@
+ALTER TABLE categories
+    ADD COLUMN parent_id UUID REFERENCES categories(id),
+    ADD COLUMN slug VARCHAR(255),
+    ADD COLUMN position INTEGER NOT NULL DEFAULT 0,
+    ADD CONSTRAINT categories_not_own_parent CHECK (parent_id <> id);
+
+-- Existing categories stay top-level; their slugs are derived from the name, with an id
+-- suffix where two names reduce to the same slug
+UPDATE categories
+SET slug = COALESCE(
+    NULLIF(trim(both '-' from regexp_replace(lower(name), '[^a-z0-9]+', '-', 'g')), ''),
+    'category'
+);
+UPDATE categories c
+SET slug = c.slug || '-' || left(c.id::text, 8)
+WHERE EXISTS (SELECT 1 FROM categories o WHERE o.slug = c.slug AND o.id < c.id);
+
+ALTER TABLE categories
+    ALTER COLUMN slug SET NOT NULL,
+    ADD CONSTRAINT categories_slug_key UNIQUE (slug);
+
+-- "Women" may appear under both Clothing and Shoes; names only need to differ among siblings
+ALTER TABLE categories DROP CONSTRAINT IF EXISTS categories_name_key;
+DROP INDEX IF EXISTS categories_name_lower_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS categories_sibling_name_idx
+ON categories (COALESCE(parent_id, '00000000-0000-0000-0000-000000000000'), LOWER(name));
+
+CREATE INDEX IF NOT EXISTS categories_parent_idx ON categories (parent_id, position);
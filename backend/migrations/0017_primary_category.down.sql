@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS product_categories_category_idx;
+DROP INDEX IF EXISTS product_categories_primary_idx;
+ALTER TABLE product_categories DROP COLUMN IF EXISTS is_primary;
@@ -0,0 +1,19 @@
+ALTER TABLE product_categories ADD COLUMN is_primary BOOLEAN NOT NULL DEFAULT false;
+
+-- Responses used to show the first category by name, so that one becomes the primary
+UPDATE product_categories pc
+SET is_primary = true
+WHERE pc.category_id = (
+    SELECT x.category_id
+    FROM product_categories x
+    JOIN categories c ON c.id = x.category_id
+    WHERE x.product_id = pc.product_id
+    ORDER BY c.name
+    LIMIT 1
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS product_categories_primary_idx
+ON product_categories (product_id) WHERE is_primary;
+
+-- Category filters look products up from the category side
+CREATE INDEX IF NOT EXISTS product_categories_category_idx ON product_categories (category_id);
@@ -0,0 +1,3 @@
+ALTER TABLE products DROP COLUMN IF EXISTS currency;
+
+ALTER TABLE products ALTER COLUMN price TYPE FLOAT8 USING price::float8;
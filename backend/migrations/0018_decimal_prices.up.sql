@@ -0,0 +1,7 @@
+-- FLOAT8 picked up rounding drift on imported prices; two places covers every supported currency
+ALTER TABLE products
+ALTER COLUMN price TYPE NUMERIC(15, 2) USING ROUND(price::numeric, 2);
+
+-- ISO-4217 code; everything priced before this column existed was in CFA francs
+ALTER TABLE products ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'XAF';
+ALTER TABLE products ADD CONSTRAINT products_currency_check CHECK (currency ~ '^[A-Z]{3}$');
@@ -0,0 +1,4 @@
+DELETE FROM verification_rules
+WHERE category_id IS NULL
+    AND config->>'kind' = 'price_range'
+    AND config->>'currency' IN ('XOF', 'NGN', 'EUR', 'USD');
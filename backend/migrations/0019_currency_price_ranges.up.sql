@@ -0,0 +1,8 @@
+-- The price rule fails products in a currency without a range, so every accepted currency gets one.
+-- XAF already has the global range from 0005.
+INSERT INTO verification_rules (category_id, config)
+VALUES
+    (NULL, '{"kind": "price_range", "currency": "XOF", "min": 500, "max": 1000000000}'),
+    (NULL, '{"kind": "price_range", "currency": "NGN", "min": 500, "max": 1000000000}'),
+    (NULL, '{"kind": "price_range", "currency": "EUR", "min": 1, "max": 1500000}'),
+    (NULL, '{"kind": "price_range", "currency": "USD", "min": 1, "max": 1500000}');
@@ -0,0 +1,4 @@
+UPDATE jobs
+SET max_attempts = 5
+WHERE kind IN ('deliver_notification', 'deliver_webhook')
+    AND status IN ('queued', 'running');
@@ -0,0 +1,5 @@
+-- Deliveries now retry for about a day; raise the limit on jobs that are still pending
+UPDATE jobs
+SET max_attempts = 15
+WHERE kind IN ('deliver_notification', 'deliver_webhook')
+    AND status IN ('queued', 'running');
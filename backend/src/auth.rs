@@ -0,0 +1,348 @@
+use crate::errors::ApiError;
+use actix_web::dev::Payload;
+use actix_web::{web, FromRequest, HttpRequest};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{Duration, Utc};
+use futures::future::LocalBoxFuture;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::env;
+use uuid::Uuid;
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+// Long enough to open the connection; EventSource cannot send headers, so these go in the URL
+pub const STREAM_TOKEN_TTL_SECONDS: i64 = 60;
+const STREAM_SCOPE: &str = "notification_stream";
+// Vendors created before authentication have no password; an operator issues one of these with
+// `product-listing-backend password-setup <email>` and the vendor redeems it once
+pub const PASSWORD_SETUP_TOKEN_TTL_HOURS: i64 = 72;
+const PASSWORD_SETUP_SCOPE: &str = "password_setup";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    Vendor,
+    Moderator,
+}
+
+#[derive(Clone)]
+pub struct AuthConfig {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    token_ttl: Duration,
+}
+
+impl AuthConfig {
+    pub fn from_env() -> Self {
+        let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+        let ttl_hours = env::var("JWT_TTL_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(24);
+        AuthConfig::new(&secret, Duration::hours(ttl_hours))
+    }
+
+    pub fn new(secret: &str, token_ttl: Duration) -> Self {
+        AuthConfig {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            token_ttl,
+        }
+    }
+
+    fn sign(
+        &self,
+        vendor_id: Uuid,
+        scope: Option<&str>,
+        ttl: Duration,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = Utc::now();
+        let claims = Claims {
+            sub: vendor_id,
+            scope: scope.map(str::to_string),
+            iat: now.timestamp(),
+            exp: (now + ttl).timestamp(),
+        };
+        encode(&Header::default(), &claims, &self.encoding_key)
+    }
+
+    pub fn issue_token(&self, vendor_id: Uuid) -> Result<String, jsonwebtoken::errors::Error> {
+        self.sign(vendor_id, None, self.token_ttl)
+    }
+
+    // Only good for opening a notification stream; rejected as a bearer token everywhere else
+    pub fn issue_stream_token(
+        &self,
+        vendor_id: Uuid,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        self.sign(
+            vendor_id,
+            Some(STREAM_SCOPE),
+            Duration::seconds(STREAM_TOKEN_TTL_SECONDS),
+        )
+    }
+
+    // Only good for choosing a first password
+    pub fn issue_password_setup_token(
+        &self,
+        vendor_id: Uuid,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        self.sign(
+            vendor_id,
+            Some(PASSWORD_SETUP_SCOPE),
+            Duration::hours(PASSWORD_SETUP_TOKEN_TTL_HOURS),
+        )
+    }
+
+    pub fn verify_password_setup_token(&self, token: &str) -> Result<Uuid, ApiError> {
+        match self.verify_token(token.trim()) {
+            Ok(claims) if claims.scope.as_deref() == Some(PASSWORD_SETUP_SCOPE) => Ok(claims.sub),
+            _ => Err(ApiError::unauthorized("Invalid or expired setup token")),
+        }
+    }
+
+    pub fn verify_stream_token(&self, token: &str) -> Result<Uuid, ApiError> {
+        match self.verify_token(token.trim()) {
+            Ok(claims) if claims.scope.as_deref() == Some(STREAM_SCOPE) => Ok(claims.sub),
+            _ => Err(ApiError::unauthorized("Invalid or expired stream token")),
+        }
+    }
+
+    fn verify_token(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        decode::<Claims>(token, &self.decoding_key, &Validation::default()).map(|data| data.claims)
+    }
+}
+
+// Identifies the vendor only; the role is read from `vendors` on each request
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: Uuid,
+    // Set on restricted tokens; full access tokens have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    iat: i64,
+    exp: i64,
+}
+
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+// Resolved from a `Authorization: Bearer <token>` header; rejects the request with 401 otherwise
+#[derive(Debug, Clone, Copy)]
+pub struct AuthenticatedVendor {
+    pub vendor_id: Uuid,
+    pub role: Role,
+}
+
+impl FromRequest for AuthenticatedVendor {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let vendor_id = match req.app_data::<web::Data<AuthConfig>>() {
+            Some(config) => {
+                let token = req
+                    .headers()
+                    .get("Authorization")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("Bearer "));
+                match token {
+                    Some(token) => match config.verify_token(token.trim()) {
+                        Ok(claims) if claims.scope.is_none() => Ok(claims.sub),
+                        _ => Err(ApiError::unauthorized("Invalid or expired token")),
+                    },
+                    None => Err(ApiError::unauthorized("Missing bearer token")),
+                }
+            }
+            None => Err(ApiError::internal("Auth is not configured")),
+        };
+        let pool = req.app_data::<web::Data<PgPool>>().cloned();
+        Box::pin(async move {
+            let pool = pool.ok_or_else(|| ApiError::internal("Database is not configured"))?;
+            AuthenticatedVendor::load(&pool, vendor_id?).await
+        })
+    }
+}
+
+impl AuthenticatedVendor {
+    // Tokens outlive role changes and deletions, so both are checked against `vendors` every time
+    pub async fn load(pool: &PgPool, vendor_id: Uuid) -> Result<Self, ApiError> {
+        let role = sqlx::query_scalar::<_, Role>("SELECT role FROM vendors WHERE id = $1")
+            .bind(vendor_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| ApiError::unauthorized("Vendor no longer exists"))?;
+        Ok(AuthenticatedVendor { vendor_id, role })
+    }
+
+    pub fn ensure_vendor(&self, vendor_id: Uuid) -> Result<(), ApiError> {
+        if self.vendor_id == vendor_id {
+            Ok(())
+        } else {
+            Err(ApiError::forbidden("You do not have access to this vendor"))
+        }
+    }
+
+    // Looks up the product's owner; 404 when it does not exist, 403 when it belongs to someone else
+    pub async fn ensure_product_owner(
+        &self,
+        pool: &PgPool,
+        product_id: Uuid,
+    ) -> Result<(), ApiError> {
+        let owner = sqlx::query_scalar::<_, Uuid>("SELECT vendor_id FROM products WHERE id = $1")
+            .bind(product_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| ApiError::not_found("Product not found"))?;
+        if owner == self.vendor_id {
+            Ok(())
+        } else {
+            Err(ApiError::forbidden(
+                "You do not have access to this product",
+            ))
+        }
+    }
+}
+
+// An authenticated account with the moderator role; everyone else gets 403
+#[derive(Debug, Clone, Copy)]
+pub struct Moderator(pub AuthenticatedVendor);
+
+impl FromRequest for Moderator {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let account = AuthenticatedVendor::from_request(req, payload);
+        Box::pin(async move {
+            match account.await {
+                Ok(account) if account.role == Role::Moderator => Ok(Moderator(account)),
+                Ok(_) => Err(ApiError::forbidden("Moderator access required")),
+                Err(e) => Err(e),
+            }
+        })
+    }
+}
+
+// Admin entry point: `password-setup <email>` prints a setup token for a vendor without a password
+pub async fn password_setup_command(
+    pool: &PgPool,
+    config: &AuthConfig,
+    args: &[String],
+) -> anyhow::Result<()> {
+    let Some(email) = args.first() else {
+        anyhow::bail!("Usage: password-setup <email>");
+    };
+    let vendor = sqlx::query_as::<_, (Uuid, bool)>(
+        "SELECT id, password_hash IS NOT NULL FROM vendors WHERE email = $1",
+    )
+    .bind(email.trim().to_lowercase())
+    .fetch_optional(pool)
+    .await?;
+    match vendor {
+        None => anyhow::bail!("No vendor with email {}", email),
+        Some((_, true)) => anyhow::bail!("{} already has a password", email),
+        Some((vendor_id, false)) => {
+            let token = config.issue_password_setup_token(vendor_id)?;
+            println!("{}", token);
+            eprintln!(
+                "Redeem with POST /api/auth/password-setup within {} hours",
+                PASSWORD_SETUP_TOKEN_TTL_HOURS
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use actix_web::ResponseError;
+
+    fn config() -> AuthConfig {
+        AuthConfig::new("test-secret", Duration::hours(1))
+    }
+
+    #[test]
+    fn password_setup_token_names_the_vendor() {
+        let config = config();
+        let vendor_id = Uuid::new_v4();
+        let token = config.issue_password_setup_token(vendor_id).unwrap();
+        assert_eq!(
+            config.verify_password_setup_token(&token).unwrap(),
+            vendor_id
+        );
+    }
+
+    #[test]
+    fn password_setup_tokens_only_set_passwords() {
+        let config = config();
+        let vendor_id = Uuid::new_v4();
+        let setup = config.issue_password_setup_token(vendor_id).unwrap();
+        assert!(config.verify_stream_token(&setup).is_err());
+        assert!(config.verify_token(&setup).unwrap().scope.is_some());
+
+        let bearer = config.issue_token(vendor_id).unwrap();
+        assert!(config.verify_password_setup_token(&bearer).is_err());
+        let other = AuthConfig::new("other-secret", Duration::hours(1));
+        assert!(other.verify_password_setup_token(&setup).is_err());
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn role_changes_and_deletions_apply_to_issued_tokens(pool: PgPool) {
+        let config = config();
+        let vendor_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO vendors (name, email, role) VALUES ('Staff', 'staff@example.com', 'moderator') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let token = config.issue_token(vendor_id).unwrap();
+        let request = || {
+            TestRequest::default()
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(pool.clone()))
+                .to_http_request()
+        };
+        assert!(Moderator::extract(&request()).await.is_ok());
+
+        sqlx::query("UPDATE vendors SET role = 'vendor' WHERE id = $1")
+            .bind(vendor_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let demoted = Moderator::extract(&request()).await.unwrap_err();
+        assert_eq!(demoted.status_code(), StatusCode::FORBIDDEN);
+        let vendor = AuthenticatedVendor::extract(&request()).await.unwrap();
+        assert_eq!(vendor.role, Role::Vendor);
+
+        sqlx::query("DELETE FROM vendors WHERE id = $1")
+            .bind(vendor_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let deleted = AuthenticatedVendor::extract(&request()).await.unwrap_err();
+        assert_eq!(deleted.status_code(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,147 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use log::error;
+use serde::Serialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{message}")]
+    Conflict {
+        message: String,
+        details: Option<Value>,
+    },
+    #[error("{message}")]
+    Unprocessable {
+        message: String,
+        details: Option<Value>,
+    },
+    // Only the log sees the underlying cause; clients get a generic message
+    #[error("Database error: {0}")]
+    Database(sqlx::Error),
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody<'a> {
+    code: &'a str,
+    message: String,
+    details: Option<&'a Value>,
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::BadRequest(message.into())
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::Conflict {
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::Internal(message.into())
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict { .. } => "conflict",
+            ApiError::Unprocessable { .. } => "unprocessable_entity",
+            ApiError::Database(_) | ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    fn details(&self) -> Option<&Value> {
+        match self {
+            ApiError::Conflict { details, .. } | ApiError::Unprocessable { details, .. } => {
+                details.as_ref()
+            }
+            _ => None,
+        }
+    }
+
+    // Replaces the message of a conflict while keeping its details, e.g. to name the duplicated field
+    pub fn on_conflict(self, message: impl Into<String>) -> Self {
+        match self {
+            ApiError::Conflict { details, .. } => ApiError::Conflict {
+                message: message.into(),
+                details,
+            },
+            other => other,
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::RowNotFound => ApiError::not_found("Resource not found"),
+            sqlx::Error::Database(db) if db.is_unique_violation() => ApiError::Conflict {
+                message: "Resource already exists".to_string(),
+                details: db.constraint().map(|c| json!({ "constraint": c })),
+            },
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => ApiError::Unprocessable {
+                message: "Referenced resource does not exist or is still in use".to_string(),
+                details: db.constraint().map(|c| json!({ "constraint": c })),
+            },
+            _ => ApiError::Database(e),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict { .. } => StatusCode::CONFLICT,
+            ApiError::Unprocessable { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Database(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let message = match self {
+            ApiError::Database(_) | ApiError::Internal(_) => {
+                error!("{}", self);
+                "Internal server error".to_string()
+            }
+            _ => self.to_string(),
+        };
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            code: self.code(),
+            message,
+            details: self.details(),
+        })
+    }
+}
@@ -0,0 +1,167 @@
+use crate::auth::{
+    hash_password, verify_password, AuthConfig, AuthenticatedVendor, MIN_PASSWORD_LENGTH,
+};
+use crate::errors::ApiError;
+use crate::i18n::{Locale, Message};
+use crate::models::*;
+use crate::validation::ValidationErrors;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn login(
+    credentials: web::Json<LoginRequest>,
+    pool: web::Data<PgPool>,
+    auth: web::Data<AuthConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let credentials = credentials.into_inner();
+    let row = sqlx::query_as::<_, (Uuid, Option<String>)>(
+        "SELECT id, password_hash FROM vendors WHERE email = $1",
+    )
+    .bind(credentials.email.trim().to_lowercase())
+    .fetch_optional(pool.get_ref())
+    .await?;
+    let vendor_id = match row {
+        Some((id, Some(hash))) if verify_password(&credentials.password, &hash) => id,
+        _ => return Err(ApiError::unauthorized("Invalid email or password")),
+    };
+    let vendor = sqlx::query_as::<_, Vendor>("SELECT * FROM vendors WHERE id = $1")
+        .bind(vendor_id)
+        .fetch_one(pool.get_ref())
+        .await?;
+    let token = auth
+        .issue_token(vendor_id)
+        .map_err(|e| ApiError::internal(format!("Failed to issue token: {}", e)))?;
+    Ok(HttpResponse::Ok().json(LoginResponse { token, vendor }))
+}
+
+// Gives a vendor created before authentication their first password and signs them in.
+// Only works while the vendor has none, so each setup token can be redeemed once.
+pub async fn complete_password_setup(
+    request: web::Json<PasswordSetupRequest>,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+    auth: web::Data<AuthConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let request = request.into_inner();
+    let vendor_id = auth.verify_password_setup_token(&request.token)?;
+    let mut errors = ValidationErrors::new(locale);
+    if request.password.chars().count() < MIN_PASSWORD_LENGTH {
+        errors.add(
+            "password",
+            Message::new("validation-password-too-short").arg("min", MIN_PASSWORD_LENGTH),
+        );
+    }
+    errors.into_result()?;
+    let password_hash = hash_password(&request.password)
+        .map_err(|e| ApiError::internal(format!("Failed to hash password: {}", e)))?;
+    let vendor = sqlx::query_as::<_, Vendor>(
+        "UPDATE vendors SET password_hash = $1 WHERE id = $2 AND password_hash IS NULL RETURNING *",
+    )
+    .bind(password_hash)
+    .bind(vendor_id)
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::conflict("This account already has a password; sign in instead"))?;
+    let token = auth
+        .issue_token(vendor.id)
+        .map_err(|e| ApiError::internal(format!("Failed to issue token: {}", e)))?;
+    Ok(HttpResponse::Ok().json(LoginResponse { token, vendor }))
+}
+
+pub async fn current_vendor(
+    vendor: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor = sqlx::query_as::<_, Vendor>("SELECT * FROM vendors WHERE id = $1")
+        .bind(vendor.vendor_id)
+        .fetch_optional(pool.get_ref())
+        .await?
+        // The token outlived the vendor it was issued for
+        .ok_or_else(|| ApiError::unauthorized("Vendor no longer exists"))?;
+    Ok(HttpResponse::Ok().json(vendor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::http::StatusCode;
+    use chrono::Duration;
+
+    async fn hashless_vendor(pool: &PgPool) -> Uuid {
+        sqlx::query_scalar("INSERT INTO vendors (name, email) VALUES ($1, $2) RETURNING id")
+            .bind("Bafoussam Crafts")
+            .bind("crafts@example.com")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn sign_in(pool: &PgPool, auth: &AuthConfig, password: &str) -> StatusCode {
+        let credentials = LoginRequest {
+            email: "crafts@example.com".to_string(),
+            password: password.to_string(),
+        };
+        match login(
+            web::Json(credentials),
+            web::Data::new(pool.clone()),
+            web::Data::new(auth.clone()),
+        )
+        .await
+        {
+            Ok(response) => response.status(),
+            Err(e) => actix_web::ResponseError::status_code(&e),
+        }
+    }
+
+    async fn set_up(
+        pool: &PgPool,
+        auth: &AuthConfig,
+        token: &str,
+        password: &str,
+    ) -> Result<HttpResponse, ApiError> {
+        complete_password_setup(
+            web::Json(PasswordSetupRequest {
+                token: token.to_string(),
+                password: password.to_string(),
+            }),
+            Locale::En,
+            web::Data::new(pool.clone()),
+            web::Data::new(auth.clone()),
+        )
+        .await
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn hashless_vendor_sets_a_password_once(pool: PgPool) {
+        let auth = AuthConfig::new("test-secret", Duration::hours(1));
+        let vendor_id = hashless_vendor(&pool).await;
+        assert_eq!(
+            sign_in(&pool, &auth, "a new password").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let token = auth.issue_password_setup_token(vendor_id).unwrap();
+        assert!(set_up(&pool, &auth, &token, "short").await.is_err());
+        let response = set_up(&pool, &auth, &token, "a new password")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value =
+            serde_json::from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(body["vendor"]["id"], vendor_id.to_string());
+        assert!(body["token"].is_string());
+
+        assert_eq!(
+            sign_in(&pool, &auth, "a new password").await,
+            StatusCode::OK
+        );
+        let reused = set_up(&pool, &auth, &token, "another password").await;
+        assert!(matches!(reused, Err(ApiError::Conflict { .. })));
+        assert_eq!(
+            sign_in(&pool, &auth, "another password").await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
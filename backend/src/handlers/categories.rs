@@ -1,13 +1,542 @@
+use crate::auth::Moderator;
+use crate::errors::ApiError;
+use crate::i18n::Locale;
 use crate::models::*;
-use actix_web::{web, HttpResponse, Responder};
-use sqlx::PgPool;
+use crate::validation::validate_category;
+use actix_web::{web, HttpResponse};
+use log::info;
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
 
-pub async fn get_categories(pool: web::Data<PgPool>) -> impl Responder {
-    match sqlx::query_as::<_, Category>("SELECT * FROM categories")
+const CATEGORY_NAME_TAKEN: &str = "A category with this name already exists at this level";
+const CATEGORY_SLUG_TAKEN: &str = "A category with this slug already exists";
+
+// Both the sibling-name index and the slug constraint surface as conflicts; name the one that fired
+fn category_conflict(e: sqlx::Error) -> ApiError {
+    let error = ApiError::from(e);
+    let slug_taken = matches!(
+        &error,
+        ApiError::Conflict { details: Some(details), .. }
+            if details["constraint"] == "categories_slug_key"
+    );
+    if slug_taken {
+        error.on_conflict(CATEGORY_SLUG_TAKEN)
+    } else {
+        error.on_conflict(CATEGORY_NAME_TAKEN)
+    }
+}
+
+// Lowercase ASCII words joined by dashes; French accents are folded so "Épicerie fine" becomes "epicerie-fine"
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        let folded = match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => "a",
+            'æ' => "ae",
+            'ç' => "c",
+            'è' | 'é' | 'ê' | 'ë' => "e",
+            'ì' | 'í' | 'î' | 'ï' => "i",
+            'ñ' => "n",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => "o",
+            'œ' => "oe",
+            'ù' | 'ú' | 'û' | 'ü' => "u",
+            'ý' | 'ÿ' => "y",
+            c if c.is_ascii_alphanumeric() => {
+                slug.push(c);
+                continue;
+            }
+            _ => {
+                if !slug.is_empty() && !slug.ends_with('-') {
+                    slug.push('-');
+                }
+                continue;
+            }
+        };
+        slug.push_str(folded);
+    }
+    let slug = slug.trim_end_matches('-');
+    // An id-shaped slug would be looked up as an id
+    if slug.is_empty() || Uuid::parse_str(slug).is_ok() {
+        "category".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+// Appends -2, -3, ... until the slug is free
+async fn unique_slug(conn: &mut PgConnection, base: &str) -> Result<String, sqlx::Error> {
+    let taken = sqlx::query_scalar::<_, String>(
+        "SELECT slug FROM categories WHERE slug = $1 OR slug LIKE $1 || '-%'",
+    )
+    .bind(base)
+    .fetch_all(conn)
+    .await?;
+    Ok(first_free_slug(base, &taken))
+}
+
+fn first_free_slug(base: &str, taken: &[String]) -> String {
+    let mut slug = base.to_string();
+    let mut suffix = 2;
+    while taken.contains(&slug) {
+        slug = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    slug
+}
+
+async fn is_in_subtree(
+    conn: &mut PgConnection,
+    root_id: Uuid,
+    category_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        WITH RECURSIVE subtree AS (
+            SELECT id FROM categories WHERE id = $1
+            UNION ALL
+            SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+        )
+        SELECT EXISTS (SELECT 1 FROM subtree WHERE id = $2)
+        "#,
+    )
+    .bind(root_id)
+    .bind(category_id)
+    .fetch_one(conn)
+    .await
+}
+
+// Moves and merges read the tree before changing it; serializing them keeps two concurrent
+// moves from forming a cycle
+async fn lock_tree(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("LOCK TABLE categories IN SHARE ROW EXCLUSIVE MODE")
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+// Flat list in display order: siblings by position, then name
+pub async fn get_categories(
+    params: web::Query<CountParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    if params.with_counts {
+        let categories = sqlx::query_as::<_, CategoryWithCounts>(
+            r#"
+            WITH RECURSIVE subtree AS (
+                SELECT id AS root_id, id FROM categories
+                UNION ALL
+                SELECT s.root_id, c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+            )
+            SELECT c.*,
+                COUNT(DISTINCT p.id) FILTER (WHERE p.status = 'live') AS live,
+                COUNT(DISTINCT p.id) FILTER (WHERE p.status = 'draft') AS draft,
+                COUNT(DISTINCT p.id) FILTER (WHERE p.status = 'rejected') AS rejected,
+                COUNT(DISTINCT p.id) AS total
+            FROM categories c
+            JOIN subtree s ON s.root_id = c.id
+            LEFT JOIN product_categories pc ON pc.category_id = s.id
+            LEFT JOIN products p ON p.id = pc.product_id
+            GROUP BY c.id
+            ORDER BY c.position, c.name
+            "#,
+        )
         .fetch_all(pool.get_ref())
+        .await?;
+        return Ok(HttpResponse::Ok().json(categories));
+    }
+    let categories =
+        sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY position, name")
+            .fetch_all(pool.get_ref())
+            .await?;
+    Ok(HttpResponse::Ok().json(categories))
+}
+
+pub async fn get_category_tree(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    let categories =
+        sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY position, name")
+            .fetch_all(pool.get_ref())
+            .await?;
+    let mut children: HashMap<Option<Uuid>, Vec<Category>> = HashMap::new();
+    for category in categories {
+        children
+            .entry(category.parent_id)
+            .or_default()
+            .push(category);
+    }
+    Ok(HttpResponse::Ok().json(build_tree(None, &mut children)))
+}
+
+fn build_tree(
+    parent_id: Option<Uuid>,
+    children: &mut HashMap<Option<Uuid>, Vec<Category>>,
+) -> Vec<CategoryNode> {
+    children
+        .remove(&parent_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|category| CategoryNode {
+            children: build_tree(Some(category.id), children),
+            category,
+        })
+        .collect()
+}
+
+// Accepts either the id or the slug
+pub async fn get_category(
+    key: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let key = key.into_inner();
+    let category = match Uuid::parse_str(&key) {
+        Ok(id) => {
+            sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = $1")
+                .bind(id)
+                .fetch_optional(pool.get_ref())
+                .await?
+        }
+        Err(_) => {
+            sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE slug = $1")
+                .bind(key)
+                .fetch_optional(pool.get_ref())
+                .await?
+        }
+    }
+    .ok_or_else(|| ApiError::not_found("Category not found"))?;
+    Ok(HttpResponse::Ok().json(category))
+}
+
+// From the top-level ancestor down to the category itself
+pub async fn get_category_breadcrumb(
+    category_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let path = sqlx::query_as::<_, Category>(
+        r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT c.*, 0 AS depth FROM categories c WHERE c.id = $1
+            UNION ALL
+            SELECT c.*, a.depth + 1 FROM categories c JOIN ancestors a ON c.id = a.parent_id
+        )
+        SELECT id, name, slug, parent_id, position, created_at
+        FROM ancestors
+        ORDER BY depth DESC
+        "#,
+    )
+    .bind(category_id.into_inner())
+    .fetch_all(pool.get_ref())
+    .await?;
+    if path.is_empty() {
+        return Err(ApiError::not_found("Category not found"));
+    }
+    Ok(HttpResponse::Ok().json(path))
+}
+
+pub async fn create_category(
+    category: web::Json<NewCategory>,
+    _moderator: Moderator,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let category = category.into_inner();
+    validate_category(pool.get_ref(), &category, locale).await?;
+    let name = category.name.trim();
+    let mut tx = pool.begin().await?;
+    let slug = match category.slug {
+        Some(slug) => slug,
+        None => unique_slug(&mut tx, &slugify(name)).await?,
+    };
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        INSERT INTO categories (name, slug, parent_id, position)
+        VALUES ($1, $2, $3, COALESCE($4, (
+            SELECT COALESCE(MAX(position) + 1, 0) FROM categories
+            WHERE parent_id IS NOT DISTINCT FROM $3
+        )))
+        RETURNING *
+        "#,
+    )
+    .bind(name)
+    .bind(slug)
+    .bind(category.parent_id.flatten())
+    .bind(category.position)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(category_conflict)?;
+    tx.commit().await?;
+    info!("Created category: {:?}", category);
+    Ok(HttpResponse::Created().json(category))
+}
+
+// Renames and moves; the whole subtree moves with the category
+pub async fn update_category(
+    category_id: web::Path<Uuid>,
+    category: web::Json<NewCategory>,
+    _moderator: Moderator,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let category_id = category_id.into_inner();
+    let category = category.into_inner();
+    validate_category(pool.get_ref(), &category, locale).await?;
+    let mut tx = pool.begin().await?;
+    lock_tree(&mut tx).await?;
+    if let Some(parent_id) = category.parent() {
+        if is_in_subtree(&mut tx, category_id, parent_id).await? {
+            return Err(ApiError::conflict(
+                "A category cannot be moved under itself or one of its subcategories",
+            ));
+        }
+    }
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        UPDATE categories
+        SET name = $1,
+            parent_id = CASE WHEN $6 THEN $2 ELSE parent_id END,
+            slug = COALESCE($3, slug),
+            position = COALESCE($4, position)
+        WHERE id = $5
+        RETURNING *
+        "#,
+    )
+    .bind(category.name.trim())
+    .bind(category.parent_id.flatten())
+    .bind(category.slug)
+    .bind(category.position)
+    .bind(category_id)
+    .bind(category.parent_id.is_some())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(category_conflict)?
+    .ok_or_else(|| ApiError::not_found("Category not found"))?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(category))
+}
+
+// Products and subcategories of the source end up in the target; the source and its
+// verification rules are removed
+pub async fn merge_category(
+    category_id: web::Path<Uuid>,
+    request: web::Json<MergeRequest>,
+    _moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let source_id = category_id.into_inner();
+    let target_id = request.into;
+    if source_id == target_id {
+        return Err(ApiError::bad_request(
+            "A category cannot be merged into itself",
+        ));
+    }
+    let mut tx = pool.begin().await?;
+    lock_tree(&mut tx).await?;
+    let found = sqlx::query_scalar::<_, Uuid>("SELECT id FROM categories WHERE id = ANY($1)")
+        .bind([source_id, target_id])
+        .fetch_all(&mut *tx)
+        .await?;
+    if !found.contains(&source_id) {
+        return Err(ApiError::not_found("Category not found"));
+    }
+    if !found.contains(&target_id) {
+        return Err(ApiError::not_found("Target category not found"));
+    }
+    if is_in_subtree(&mut tx, source_id, target_id).await? {
+        return Err(ApiError::conflict(
+            "A category cannot be merged into one of its subcategories",
+        ));
+    }
+    // The target takes over as primary wherever the source was, including products that
+    // already had the target as a secondary category
+    let moved = sqlx::query(
+        r#"
+        WITH removed AS (
+            DELETE FROM product_categories WHERE category_id = $1
+            RETURNING product_id, is_primary
+        )
+        INSERT INTO product_categories (product_id, category_id, is_primary)
+        SELECT product_id, $2, is_primary FROM removed
+        ON CONFLICT (product_id, category_id)
+        DO UPDATE SET is_primary = product_categories.is_primary OR EXCLUDED.is_primary
+        "#,
+    )
+    .bind(source_id)
+    .bind(target_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    sqlx::query("UPDATE categories SET parent_id = $2 WHERE parent_id = $1")
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(category_conflict)?;
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+    let target = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = $1")
+        .bind(target_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    info!(
+        "Merged category {} into {} ({} products moved)",
+        source_id, target_id, moved
+    );
+    Ok(HttpResponse::Ok().json(target))
+}
+
+// Categories still assigned to products must be merged instead, so no product loses its category;
+// subcategories have to be moved or deleted first
+pub async fn delete_category(
+    category_id: web::Path<Uuid>,
+    _moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let category_id = category_id.into_inner();
+    let mut tx = pool.begin().await?;
+    let exists =
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM categories WHERE id = $1 FOR UPDATE")
+            .bind(category_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if exists.is_none() {
+        return Err(ApiError::not_found("Category not found"));
+    }
+    let (products, subcategories) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM product_categories WHERE category_id = $1),
+            (SELECT COUNT(*) FROM categories WHERE parent_id = $1)
+        "#,
+    )
+    .bind(category_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if products > 0 {
+        return Err(ApiError::Conflict {
+            message:
+                "Category is still assigned to products; merge it into another category instead"
+                    .to_string(),
+            details: Some(json!({ "products": products })),
+        });
+    }
+    if subcategories > 0 {
+        return Err(ApiError::Conflict {
+            message: "Category still has subcategories; move or delete them first".to_string(),
+            details: Some(json!({ "subcategories": subcategories })),
+        });
+    }
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(category_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json("Category deleted successfully"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    #[test]
+    fn slugify_folds_accents_and_joins_words() {
+        assert_eq!(slugify("Épicerie fine"), "epicerie-fine");
+        assert_eq!(slugify("Cœur & Âme"), "coeur-ame");
+        assert_eq!(slugify("  Sacs -- en cuir!  "), "sacs-en-cuir");
+    }
+
+    #[test]
+    fn slugify_falls_back_when_nothing_usable_is_left() {
+        assert_eq!(slugify(""), "category");
+        assert_eq!(slugify("!!!"), "category");
+        assert_eq!(slugify("550E8400-E29B-41D4-A716-446655440000"), "category");
+    }
+
+    #[test]
+    fn first_free_slug_keeps_a_free_base() {
+        assert_eq!(first_free_slug("bags", &[]), "bags");
+        assert_eq!(first_free_slug("bags", &["bags-2".to_string()]), "bags");
+    }
+
+    #[test]
+    fn first_free_slug_appends_the_lowest_free_suffix() {
+        let taken = ["bags", "bags-2", "bags-4"].map(String::from);
+        assert_eq!(first_free_slug("bags", &taken), "bags-3");
+        let taken = ["bags", "bags-2", "bags-3"].map(String::from);
+        assert_eq!(first_free_slug("bags", &taken), "bags-4");
+    }
+
+    async fn insert_category(pool: &PgPool, slug: &str, parent_id: Option<Uuid>) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO categories (name, slug, parent_id) VALUES ($1, $1, $2) RETURNING id",
+        )
+        .bind(slug)
+        .bind(parent_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn insert_product(pool: &PgPool, vendor_id: Uuid, status: &str, categories: &[Uuid]) {
+        let product_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO products (vendor_id, name, description, price, image_url, status)
+            VALUES ($1, 'Ndop cloth', 'Hand-dyed', 15000, '', $2::product_status)
+            RETURNING id
+            "#,
+        )
+        .bind(vendor_id)
+        .bind(status)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for category_id in categories {
+            sqlx::query("INSERT INTO product_categories (product_id, category_id) VALUES ($1, $2)")
+                .bind(product_id)
+                .bind(category_id)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn counts_cover_the_whole_subtree(pool: PgPool) {
+        let vendor_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO vendors (name, email) VALUES ('Ndop Weavers', 'ndop@example.com') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let parent = insert_category(&pool, "textiles", None).await;
+        let child = insert_category(&pool, "ndop", Some(parent)).await;
+        let leaf = insert_category(&pool, "ndop-indigo", Some(child)).await;
+        insert_product(&pool, vendor_id, "live", &[leaf]).await;
+        // Listed at two levels of the same subtree, counted once
+        insert_product(&pool, vendor_id, "draft", &[parent, child]).await;
+
+        let response = get_categories(
+            web::Query(CountParams { with_counts: true }),
+            web::Data::new(pool.clone()),
+        )
         .await
-    {
-        Ok(categories) => HttpResponse::Ok().json(categories),
-        Err(e) => HttpResponse::InternalServerError().json(format!("Error: {}", e)),
+        .unwrap();
+        let body: Vec<serde_json::Value> =
+            serde_json::from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap();
+        let counts = |id: Uuid| {
+            let category = body.iter().find(|c| c["id"] == id.to_string()).unwrap();
+            let counts = &category["product_counts"];
+            (
+                counts["live"].clone(),
+                counts["draft"].clone(),
+                counts["total"].clone(),
+            )
+        };
+        assert_eq!(counts(parent), (json!(1), json!(1), json!(2)));
+        assert_eq!(counts(child), (json!(1), json!(1), json!(2)));
+        assert_eq!(counts(leaf), (json!(1), json!(0), json!(1)));
     }
 }
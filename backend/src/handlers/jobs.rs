@@ -0,0 +1,94 @@
+use crate::auth::{AuthenticatedVendor, Moderator, Role};
+use crate::errors::ApiError;
+use crate::models::*;
+use actix_web::{web, HttpResponse};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+// Visible to the account that enqueued the job and to moderators
+pub async fn get_job(
+    job_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
+        .bind(job_id.into_inner())
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::not_found("Job not found"))?;
+    if auth.role != Role::Moderator && job.owner_id != Some(auth.vendor_id) {
+        return Err(ApiError::forbidden("You do not have access to this job"));
+    }
+    Ok(HttpResponse::Ok().json(job))
+}
+
+// Operators use `?status=dead` to inspect the dead-letter queue
+pub async fn get_jobs(
+    _moderator: Moderator,
+    filters: web::Query<JobFilters>,
+    params: web::Query<ListParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = params.limit();
+    let offset = params.offset();
+    let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM jobs WHERE TRUE");
+    let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM jobs WHERE TRUE");
+    if let Some(status) = filters.status {
+        count.push(" AND status = ").push_bind(status);
+        query.push(" AND status = ").push_bind(status);
+    }
+    let total = count
+        .build_query_scalar::<i64>()
+        .fetch_one(pool.get_ref())
+        .await?;
+    query
+        .push(" ORDER BY updated_at DESC, id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let items = query
+        .build_query_as::<Job>()
+        .fetch_all(pool.get_ref())
+        .await?;
+    Ok(HttpResponse::Ok().json(Page {
+        items,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+// Gives a dead job a fresh set of attempts
+pub async fn retry_job(
+    job_id: web::Path<Uuid>,
+    _moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let job_id = job_id.into_inner();
+    let job = sqlx::query_as::<_, Job>(
+        r#"
+        UPDATE jobs
+        SET status = 'queued', attempts = 0, run_at = NOW(), updated_at = NOW()
+        WHERE id = $1 AND status = 'dead'
+        RETURNING *
+        "#,
+    )
+    .bind(job_id)
+    .fetch_optional(pool.get_ref())
+    .await?;
+    match job {
+        Some(job) => Ok(HttpResponse::Ok().json(job)),
+        None => {
+            let exists =
+                sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM jobs WHERE id = $1)")
+                    .bind(job_id)
+                    .fetch_one(pool.get_ref())
+                    .await?;
+            Err(if exists {
+                ApiError::conflict("Only dead jobs can be retried")
+            } else {
+                ApiError::not_found("Job not found")
+            })
+        }
+    }
+}
@@ -1,14 +1,24 @@
+pub mod auth;
 pub mod categories;
+pub mod jobs;
+pub mod moderation;
 pub mod notifications;
 pub mod products;
 pub mod tags;
+pub mod vendors;
+pub mod webhooks;
 
 use actix_web::{HttpResponse, Responder};
 
+pub use auth::*;
 pub use categories::*;
+pub use jobs::*;
+pub use moderation::*;
 pub use notifications::*;
 pub use products::*;
 pub use tags::*;
+pub use vendors::*;
+pub use webhooks::*;
 
 pub async fn health_check() -> impl Responder {
     HttpResponse::Ok().json("Backend is running!")
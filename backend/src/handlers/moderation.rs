@@ -0,0 +1,175 @@
+use crate::auth::Moderator;
+use crate::errors::ApiError;
+use crate::lifecycle;
+use crate::models::*;
+use crate::notifications::notify_product_vendor;
+use actix_web::{web, HttpResponse};
+use log::info;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// Oldest first so nothing waits in the queue indefinitely
+pub async fn get_moderation_queue(
+    _moderator: Moderator,
+    params: web::Query<ListParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = params.limit();
+    let offset = params.offset();
+    let total =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM products WHERE status = 'in_review'")
+            .fetch_one(pool.get_ref())
+            .await?;
+    let items = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT * FROM products
+        WHERE status = 'in_review'
+        ORDER BY updated_at ASC, id ASC
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(Page {
+        items,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+pub async fn approve_product(
+    product_id: web::Path<Uuid>,
+    request: Option<web::Json<TransitionRequest>>,
+    moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let reason = request.and_then(|r| non_empty_reason(r.into_inner()));
+    let transition = decide(
+        pool.get_ref(),
+        product_id.into_inner(),
+        moderator,
+        ProductStatus::Live,
+        reason,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(transition))
+}
+
+pub async fn reject_product(
+    product_id: web::Path<Uuid>,
+    request: web::Json<TransitionRequest>,
+    moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let reason = non_empty_reason(request.into_inner())
+        .ok_or_else(|| ApiError::bad_request("A reason is required to reject a product"))?;
+    let transition = decide(
+        pool.get_ref(),
+        product_id.into_inner(),
+        moderator,
+        ProductStatus::Rejected,
+        Some(reason),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(transition))
+}
+
+// Takes a live product off the marketplace, e.g. after a complaint
+pub async fn suspend_product(
+    product_id: web::Path<Uuid>,
+    request: web::Json<TransitionRequest>,
+    moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let reason = non_empty_reason(request.into_inner())
+        .ok_or_else(|| ApiError::bad_request("A reason is required to suspend a product"))?;
+    let transition = decide(
+        pool.get_ref(),
+        product_id.into_inner(),
+        moderator,
+        ProductStatus::Suspended,
+        Some(reason),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(transition))
+}
+
+pub async fn reinstate_product(
+    product_id: web::Path<Uuid>,
+    request: Option<web::Json<TransitionRequest>>,
+    moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let reason = request.and_then(|r| non_empty_reason(r.into_inner()));
+    let product_id = product_id.into_inner();
+    let current =
+        sqlx::query_scalar::<_, ProductStatus>("SELECT status FROM products WHERE id = $1")
+            .bind(product_id)
+            .fetch_optional(pool.get_ref())
+            .await?
+            .ok_or_else(|| ApiError::not_found("Product not found"))?;
+    // Live is also reachable from in_review, which must go through approve instead
+    if current != ProductStatus::Suspended {
+        return Err(ApiError::conflict(
+            "Only suspended products can be reinstated",
+        ));
+    }
+    let transition = decide(
+        pool.get_ref(),
+        product_id,
+        moderator,
+        ProductStatus::Live,
+        reason,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(transition))
+}
+
+fn non_empty_reason(request: TransitionRequest) -> Option<String> {
+    request
+        .reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+}
+
+async fn decide(
+    pool: &PgPool,
+    product_id: Uuid,
+    Moderator(moderator): Moderator,
+    to: ProductStatus,
+    reason: Option<String>,
+) -> Result<StatusTransition, ApiError> {
+    let mut tx = pool.begin().await?;
+    // The row lock taken by transition() makes concurrent decisions on the same product safe
+    let transition = lifecycle::transition(
+        &mut tx,
+        product_id,
+        to,
+        Some(moderator.vendor_id),
+        reason.as_deref(),
+    )
+    .await?;
+    let kind = match (transition.from_status, to) {
+        (ProductStatus::Suspended, ProductStatus::Live) => NotificationKind::ProductReinstated,
+        (_, ProductStatus::Live) => NotificationKind::ModerationApproved,
+        (_, ProductStatus::Suspended) => NotificationKind::ProductSuspended,
+        _ => NotificationKind::ModerationRejected,
+    };
+    let payload = NotificationPayload {
+        reason,
+        ..Default::default()
+    };
+    notify_product_vendor(&mut tx, product_id, kind, payload).await?;
+    tx.commit().await?;
+    info!(
+        "Moderator {} moved product {} from {} to {}",
+        moderator.vendor_id,
+        product_id,
+        transition.from_status.as_str(),
+        to.as_str()
+    );
+    Ok(transition)
+}
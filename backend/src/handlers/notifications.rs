@@ -1,25 +1,649 @@
+use crate::auth::{AuthConfig, AuthenticatedVendor, STREAM_TOKEN_TTL_SECONDS};
+use crate::errors::ApiError;
+use crate::i18n::{vendor_locale, Locale};
 use crate::models::*;
-use actix_web::{web, HttpResponse, Responder};
-use sqlx::PgPool;
+use crate::notifications::templates::rendered;
+use crate::notifications::{NotificationEvent, NotificationHub};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::{Interval, MissedTickBehavior};
 use uuid::Uuid;
 
+// Upper bound for a single bulk mark-as-read request
+const MAX_BULK_IDS: usize = 500;
+
 pub async fn get_notifications(
     vendor_id: web::Path<Uuid>,
+    filters: web::Query<NotificationFilters>,
+    params: web::Query<ListParams>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
     pool: web::Data<PgPool>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let vendor_id = vendor_id.into_inner();
-    match sqlx::query_as::<_, Notification>(
+    auth.ensure_vendor(vendor_id)?;
+    let limit = params.limit();
+    let offset = params.offset();
+
+    let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM notifications");
+    push_notification_filters(&mut count, vendor_id, &filters);
+    let total = count
+        .build_query_scalar::<i64>()
+        .fetch_one(pool.get_ref())
+        .await?;
+
+    let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM notifications");
+    push_notification_filters(&mut query, vendor_id, &filters);
+    query
+        .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let items = query
+        .build_query_as::<Notification>()
+        .fetch_all(pool.get_ref())
+        .await?
+        .into_iter()
+        .map(|notification| rendered(notification, locale))
+        .collect::<Vec<_>>();
+    Ok(HttpResponse::Ok().json(Page {
+        items,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+fn push_notification_filters(
+    query: &mut QueryBuilder<'_, Postgres>,
+    vendor_id: Uuid,
+    filters: &NotificationFilters,
+) {
+    query.push(" WHERE vendor_id = ").push_bind(vendor_id);
+    if filters.archived {
+        query.push(" AND archived_at IS NOT NULL");
+    } else {
+        query.push(" AND archived_at IS NULL");
+    }
+    if let Some(is_read) = filters.is_read {
+        query.push(" AND is_read = ").push_bind(is_read);
+    }
+    if let Some(kind) = filters.kind {
+        query.push(" AND kind = ").push_bind(kind);
+    }
+}
+
+// Inbox totals per kind, e.g. to build filter chips
+pub async fn get_notification_kinds(
+    vendor_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let counts = sqlx::query_as::<_, NotificationKindCount>(
         r#"
-        SELECT * FROM notifications
-        WHERE vendor_id = $1
-        ORDER BY created_at DESC
+        SELECT kind, COUNT(*) AS total, COUNT(*) FILTER (WHERE NOT is_read) AS unread
+        FROM notifications
+        WHERE vendor_id = $1 AND archived_at IS NULL
+        GROUP BY kind
+        ORDER BY kind
         "#,
     )
     .bind(vendor_id)
     .fetch_all(pool.get_ref())
-    .await
-    {
-        Ok(notifications) => HttpResponse::Ok().json(notifications),
-        Err(e) => HttpResponse::InternalServerError().json(format!("Error: {}", e)),
+    .await?;
+    Ok(HttpResponse::Ok().json(counts))
+}
+
+pub async fn get_unread_count(
+    vendor_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let unread = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM notifications
+        WHERE vendor_id = $1 AND NOT is_read AND archived_at IS NULL
+        "#,
+    )
+    .bind(vendor_id)
+    .fetch_one(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(UnreadCount { unread }))
+}
+
+pub async fn mark_notification_read(
+    path: web::Path<(Uuid, Uuid)>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    set_read(path.into_inner(), auth, locale, pool.get_ref(), true).await
+}
+
+pub async fn mark_notification_unread(
+    path: web::Path<(Uuid, Uuid)>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    set_read(path.into_inner(), auth, locale, pool.get_ref(), false).await
+}
+
+async fn set_read(
+    (vendor_id, notification_id): (Uuid, Uuid),
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: &PgPool,
+    is_read: bool,
+) -> Result<HttpResponse, ApiError> {
+    auth.ensure_vendor(vendor_id)?;
+    let notification = sqlx::query_as::<_, Notification>(
+        r#"
+        UPDATE notifications SET is_read = $1
+        WHERE id = $2 AND vendor_id = $3
+        RETURNING *
+        "#,
+    )
+    .bind(is_read)
+    .bind(notification_id)
+    .bind(vendor_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::not_found("Notification not found"))?;
+    Ok(HttpResponse::Ok().json(rendered(notification, locale)))
+}
+
+// Ids that do not belong to the vendor are ignored rather than rejected
+pub async fn mark_notifications_read(
+    vendor_id: web::Path<Uuid>,
+    request: web::Json<NotificationIds>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let ids = request.into_inner().ids;
+    if ids.len() > MAX_BULK_IDS {
+        return Err(ApiError::bad_request(format!(
+            "At most {} notifications can be updated at once",
+            MAX_BULK_IDS
+        )));
+    }
+    let result = sqlx::query(
+        r#"
+        UPDATE notifications SET is_read = true
+        WHERE vendor_id = $1 AND id = ANY($2) AND NOT is_read
+        "#,
+    )
+    .bind(vendor_id)
+    .bind(&ids)
+    .execute(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(UpdatedCount {
+        updated: result.rows_affected(),
+    }))
+}
+
+pub async fn mark_all_notifications_read(
+    vendor_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let result = sqlx::query(
+        r#"
+        UPDATE notifications SET is_read = true
+        WHERE vendor_id = $1 AND NOT is_read AND archived_at IS NULL
+        "#,
+    )
+    .bind(vendor_id)
+    .execute(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(UpdatedCount {
+        updated: result.rows_affected(),
+    }))
+}
+
+// Archiving also marks the notification read so it stops counting towards the badge
+pub async fn archive_notification(
+    path: web::Path<(Uuid, Uuid)>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let (vendor_id, notification_id) = path.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let notification = sqlx::query_as::<_, Notification>(
+        r#"
+        UPDATE notifications
+        SET is_read = true, archived_at = COALESCE(archived_at, NOW())
+        WHERE id = $1 AND vendor_id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(notification_id)
+    .bind(vendor_id)
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Notification not found"))?;
+    Ok(HttpResponse::Ok().json(rendered(notification, locale)))
+}
+
+pub async fn delete_notification(
+    path: web::Path<(Uuid, Uuid)>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let (vendor_id, notification_id) = path.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let result = sqlx::query("DELETE FROM notifications WHERE id = $1 AND vendor_id = $2")
+        .bind(notification_id)
+        .bind(vendor_id)
+        .execute(pool.get_ref())
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Notification not found"));
+    }
+    Ok(HttpResponse::Ok().json("Notification deleted successfully"))
+}
+
+pub async fn get_channel_preferences(
+    vendor_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let preferences = channel_preferences(pool.get_ref(), vendor_id).await?;
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+// Channels left out of the request keep their current setting
+pub async fn update_channel_preferences(
+    vendor_id: web::Path<Uuid>,
+    preferences: web::Json<Vec<ChannelPreference>>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let mut tx = pool.begin().await?;
+    for preference in preferences.into_inner() {
+        sqlx::query(
+            r#"
+            INSERT INTO vendor_channel_preferences (vendor_id, channel, enabled)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (vendor_id, channel)
+            DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = NOW()
+            "#,
+        )
+        .bind(vendor_id)
+        .bind(preference.channel)
+        .bind(preference.enabled)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    let preferences = channel_preferences(pool.get_ref(), vendor_id).await?;
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+// Every known channel, with the default (enabled) filled in for those never set
+async fn channel_preferences(
+    pool: &PgPool,
+    vendor_id: Uuid,
+) -> Result<Vec<ChannelPreference>, sqlx::Error> {
+    let stored = sqlx::query_as::<_, ChannelPreference>(
+        "SELECT channel, enabled FROM vendor_channel_preferences WHERE vendor_id = $1",
+    )
+    .bind(vendor_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(ChannelKind::ALL
+        .into_iter()
+        .map(|channel| ChannelPreference {
+            channel,
+            enabled: !stored.iter().any(|p| p.channel == channel && !p.enabled),
+        })
+        .collect())
+}
+
+// Where a resumed or lagging stream re-reads from; the row id doubles as the SSE event id.
+// Live events are fetched by id instead: `created_at` is the insert time, so a row from a
+// transaction that commits late can sort before rows the stream has already passed.
+type StreamCursor = (DateTime<Utc>, Uuid);
+
+const STREAM_BATCH_SIZE: i64 = 100;
+// Ids remembered per stream so a cursor re-read does not repeat what a live event already sent
+const RECENT_IDS: usize = 256;
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+struct NotificationStream {
+    pool: PgPool,
+    vendor_id: Uuid,
+    events: broadcast::Receiver<NotificationEvent>,
+    cursor: StreamCursor,
+    locale: Locale,
+    pending: VecDeque<Notification>,
+    recent: VecDeque<Uuid>,
+    // Set while the last cursor read filled a whole batch, so more rows may be waiting behind it
+    backlog: bool,
+    keep_alive: Interval,
+}
+
+impl NotificationStream {
+    fn new(
+        pool: PgPool,
+        vendor_id: Uuid,
+        events: broadcast::Receiver<NotificationEvent>,
+        cursor: StreamCursor,
+        locale: Locale,
+    ) -> Self {
+        let mut keep_alive = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+        keep_alive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        NotificationStream {
+            pool,
+            vendor_id,
+            events,
+            cursor,
+            locale,
+            pending: VecDeque::new(),
+            recent: VecDeque::new(),
+            backlog: false,
+            keep_alive,
+        }
+    }
+
+    fn queue(&mut self, notification: Notification) {
+        if self.recent.contains(&notification.id) {
+            return;
+        }
+        if self.recent.len() == RECENT_IDS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(notification.id);
+        self.pending.push_back(notification);
+    }
+
+    async fn fetch_event(&mut self, notification_id: Uuid) -> Result<(), sqlx::Error> {
+        let notification = sqlx::query_as::<_, Notification>(
+            "SELECT * FROM notifications WHERE id = $1 AND vendor_id = $2",
+        )
+        .bind(notification_id)
+        .bind(self.vendor_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(notification) = notification {
+            // During catch-up the cursor must stay behind the rows not read yet
+            if !self.backlog {
+                self.cursor = self.cursor.max((notification.created_at, notification.id));
+            }
+            self.queue(notification);
+        }
+        Ok(())
+    }
+
+    async fn fetch_pending(&mut self) -> Result<(), sqlx::Error> {
+        let rows = sqlx::query_as::<_, Notification>(
+            r#"
+            SELECT * FROM notifications
+            WHERE vendor_id = $1 AND (created_at, id) > ($2, $3)
+            ORDER BY created_at, id
+            LIMIT $4
+            "#,
+        )
+        .bind(self.vendor_id)
+        .bind(self.cursor.0)
+        .bind(self.cursor.1)
+        .bind(STREAM_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+        self.backlog = rows.len() as i64 == STREAM_BATCH_SIZE;
+        for row in rows {
+            self.cursor = self.cursor.max((row.created_at, row.id));
+            self.queue(row);
+        }
+        Ok(())
+    }
+
+    // Waits for the next chunk to send; None once the hub has shut down
+    async fn next_chunk(&mut self) -> Option<Result<Bytes, ApiError>> {
+        loop {
+            if let Some(notification) = self.pending.pop_front() {
+                return Some(sse_event(notification, self.locale));
+            }
+            if self.backlog {
+                if let Err(e) = self.fetch_pending().await {
+                    return Some(Err(e.into()));
+                }
+                continue;
+            }
+            tokio::select! {
+                event = self.events.recv() => {
+                    let fetched = match event {
+                        Ok(event) if event.vendor_id == self.vendor_id => {
+                            self.fetch_event(event.id).await
+                        }
+                        Ok(_) => Ok(()),
+                        // Missed events are recovered by re-reading from the cursor
+                        Err(broadcast::error::RecvError::Lagged(_)) => self.fetch_pending().await,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    };
+                    if let Err(e) = fetched {
+                        return Some(Err(e.into()));
+                    }
+                }
+                _ = self.keep_alive.tick() => {
+                    return Some(Ok(Bytes::from_static(b": keep-alive\n\n")));
+                }
+            }
+        }
+    }
+}
+
+fn sse_event(notification: Notification, locale: Locale) -> Result<Bytes, ApiError> {
+    let notification = rendered(notification, locale);
+    let data = serde_json::to_string(&notification)
+        .map_err(|e| ApiError::internal(format!("Failed to encode notification: {}", e)))?;
+    Ok(Bytes::from(format!(
+        "id: {}\nevent: notification\ndata: {}\n\n",
+        notification.id, data
+    )))
+}
+
+// Short-lived token for `?token=` on the stream, for clients that cannot send an Authorization header
+pub async fn create_stream_token(
+    vendor_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    config: web::Data<AuthConfig>,
+) -> Result<HttpResponse, ApiError> {
+    auth.ensure_vendor(vendor_id.into_inner())?;
+    let token = config
+        .issue_stream_token(auth.vendor_id)
+        .map_err(|e| ApiError::internal(format!("Failed to issue token: {}", e)))?;
+    Ok(HttpResponse::Ok().json(StreamTokenResponse {
+        token,
+        expires_in: STREAM_TOKEN_TTL_SECONDS,
+    }))
+}
+
+// Server-Sent Events; clients resume with `Last-Event-ID` (header or query) after a reconnect.
+// Authenticates with a bearer header or a stream token in `?token=`.
+pub async fn stream_notifications(
+    vendor_id: web::Path<Uuid>,
+    params: web::Query<StreamParams>,
+    req: HttpRequest,
+    auth: Option<AuthenticatedVendor>,
+    locale: Locale,
+    hub: web::Data<NotificationHub>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    let auth = match (auth, &params.token) {
+        (Some(auth), _) => auth,
+        (None, Some(token)) => {
+            let stream_vendor_id = req
+                .app_data::<web::Data<AuthConfig>>()
+                .ok_or_else(|| ApiError::internal("Auth is not configured"))?
+                .verify_stream_token(token)?;
+            AuthenticatedVendor::load(pool.get_ref(), stream_vendor_id).await?
+        }
+        (None, None) => return Err(ApiError::unauthorized("Missing bearer token")),
+    };
+    auth.ensure_vendor(vendor_id)?;
+    // Without a bearer header the extractor could not see the vendor's saved language
+    let locale = vendor_locale(pool.get_ref(), vendor_id)
+        .await?
+        .unwrap_or(locale);
+    // Subscribe before reading the cursor so nothing created in between is lost
+    let events = hub.subscribe();
+    let last_event_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Uuid::parse_str(value.trim()).ok())
+        .or(params.last_event_id);
+    let resumed = match last_event_id {
+        Some(id) => {
+            sqlx::query_as::<_, StreamCursor>(
+                "SELECT created_at, id FROM notifications WHERE id = $1 AND vendor_id = $2",
+            )
+            .bind(id)
+            .bind(vendor_id)
+            .fetch_optional(pool.get_ref())
+            .await?
+        }
+        None => None,
+    };
+    let cursor = match resumed {
+        Some(cursor) => cursor,
+        None => (
+            sqlx::query_scalar::<_, DateTime<Utc>>("SELECT NOW()")
+                .fetch_one(pool.get_ref())
+                .await?,
+            Uuid::nil(),
+        ),
+    };
+
+    let mut stream =
+        NotificationStream::new(pool.get_ref().clone(), vendor_id, events, cursor, locale);
+    if resumed.is_some() {
+        stream.fetch_pending().await?;
+    }
+    let retry = futures::stream::once(async { Ok(Bytes::from_static(b"retry: 3000\n\n")) });
+    let body = futures::stream::unfold(stream, |mut stream| async move {
+        stream.next_chunk().await.map(|chunk| (chunk, stream))
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(retry.chain(body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn seed_vendor(pool: &PgPool) -> (Uuid, Uuid) {
+        let vendor_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO vendors (name, email) VALUES ('Foumban Bronze', 'bronze@example.com') RETURNING id",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        let product_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO products (vendor_id, name, description, price, image_url)
+            VALUES ($1, 'Bronze mask', 'Lost-wax cast', 25000, '')
+            RETURNING id
+            "#,
+        )
+        .bind(vendor_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        (vendor_id, product_id)
+    }
+
+    fn event_id(chunk: &Bytes) -> Uuid {
+        let text = std::str::from_utf8(chunk).unwrap();
+        let id = text.strip_prefix("id: ").unwrap().lines().next().unwrap();
+        Uuid::parse_str(id).unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn resume_delivers_a_backlog_larger_than_one_batch(pool: PgPool) {
+        let (vendor_id, product_id) = seed_vendor(&pool).await;
+        let backlog = STREAM_BATCH_SIZE as usize * 2 + 50;
+        let start: DateTime<Utc> = sqlx::query_scalar("SELECT NOW() - INTERVAL '1 hour'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let mut expected: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            INSERT INTO notifications (vendor_id, product_id, message, created_at)
+            SELECT $1, $2, 'Backlog ' || n, $3 + n * INTERVAL '1 second'
+            FROM generate_series(1, $4) AS n
+            RETURNING id
+            "#,
+        )
+        .bind(vendor_id)
+        .bind(product_id)
+        .bind(start)
+        .bind(backlog as i32)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+        // A live event is already waiting when the first batch runs out
+        let (sender, events) = broadcast::channel(16);
+        let live: Uuid = sqlx::query_scalar(
+            "INSERT INTO notifications (vendor_id, product_id, message) VALUES ($1, $2, 'Live') RETURNING id",
+        )
+        .bind(vendor_id)
+        .bind(product_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        expected.push(live);
+        sender
+            .send(NotificationEvent {
+                id: live,
+                vendor_id,
+            })
+            .unwrap();
+
+        let mut stream = NotificationStream::new(
+            pool.clone(),
+            vendor_id,
+            events,
+            (start, Uuid::nil()),
+            Locale::En,
+        );
+        stream.fetch_pending().await.unwrap();
+        let mut received = Vec::new();
+        while received.len() < expected.len() {
+            // A skipped row would otherwise leave the stream waiting forever
+            let chunk = tokio::time::timeout(Duration::from_secs(5), stream.next_chunk())
+                .await
+                .expect("stream stalled before delivering every notification")
+                .unwrap()
+                .unwrap();
+            if !chunk.starts_with(b":") {
+                received.push(event_id(&chunk));
+            }
+        }
+        assert_eq!(received, expected);
     }
 }
@@ -1,11 +1,20 @@
+use crate::auth::{AuthenticatedVendor, Role};
+use crate::errors::ApiError;
+use crate::i18n::Locale;
+use crate::jobs::{self, Task};
+use crate::lifecycle;
 use crate::models::*;
+use crate::validation::validate_product;
+use crate::verification;
+use crate::webhooks;
 use actix_multipart::Multipart;
 use actix_web::web::Json;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpResponse};
 use futures_util::StreamExt;
 use image;
-use log::{error, info};
-use sqlx::PgPool;
+use log::info;
+use serde_json::json;
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder};
 use std::fs;
 use std::path::Path;
 use uuid::Uuid;
@@ -13,64 +22,38 @@ use uuid::Uuid;
 // Product CRUD
 pub async fn create_product(
     product: web::Json<NewProduct>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
     pool: web::Data<PgPool>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     info!("Received product creation request: {:?}", product);
     let product = product.into_inner();
-    // Start a transaction
-    let mut tx = match pool.begin().await {
-        Ok(tx) => tx,
-        Err(e) => {
-            error!("Failed to start transaction: {}", e);
-            return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-        }
-    };
+    validate_product(pool.get_ref(), &product, locale).await?;
+    let category_ids = product.all_category_ids();
+    // Start a transaction; dropping it on an early return rolls it back
+    let mut tx = pool.begin().await?;
     // Insert the product
-    let product_result = match sqlx::query_as::<_, Product>(
+    let product_result = sqlx::query_as::<_, Product>(
         r#"
-        INSERT INTO products (vendor_id, name, description, price, image_url, is_draft)
+        INSERT INTO products (vendor_id, name, description, price, currency, image_url)
         VALUES ($1, $2, $3, $4, $5, $6)
         RETURNING *
         "#,
     )
-    .bind(product.vendor_id)
+    .bind(auth.vendor_id)
     .bind(product.name)
     .bind(product.description)
     .bind(product.price)
+    .bind(product.currency)
     .bind(product.image_url)
-    .bind(product.is_draft)
     .fetch_one(&mut *tx)
-    .await
-    {
-        Ok(product) => product,
-        Err(e) => {
-            error!("Failed to create product: {}", e);
-            let _ = tx.rollback().await;
-            return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-        }
-    };
-    // Insert category if provided
-    if let Some(category_id) = product.category_id {
-        if let Err(e) = sqlx::query(
-            r#"
-            INSERT INTO product_categories (product_id, category_id)
-            VALUES ($1, $2)
-            "#,
-        )
-        .bind(product_result.id)
-        .bind(category_id)
-        .execute(&mut *tx)
-        .await
-        {
-            error!("Failed to add category: {}", e);
-            let _ = tx.rollback().await;
-            return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-        }
-    }
+    .await?;
+    // Insert categories if provided
+    insert_product_categories(&mut tx, product_result.id, &category_ids).await?;
     // Insert tags if provided
     if let Some(tag_ids) = product.tag_ids {
         for tag_id in tag_ids {
-            if let Err(e) = sqlx::query(
+            sqlx::query(
                 r#"
                 INSERT INTO product_tags (product_id, tag_id)
                 VALUES ($1, $2)
@@ -79,281 +62,768 @@ pub async fn create_product(
             .bind(product_result.id)
             .bind(tag_id)
             .execute(&mut *tx)
-            .await
-            {
-                error!("Failed to add tag: {}", e);
-                let _ = tx.rollback().await;
-                return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-            }
+            .await?;
         }
     }
-    // Commit the transaction
-    if let Err(e) = tx.commit().await {
-        error!("Failed to commit transaction: {}", e);
-        return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-    }
+    webhooks::emit(
+        &mut tx,
+        WebhookEvent::Created,
+        product_result.vendor_id,
+        &json!({ "product": product_result }),
+    )
+    .await?;
+    tx.commit().await?;
     info!("Successfully created product: {:?}", product_result);
-    HttpResponse::Created().json(product_result)
+    Ok(HttpResponse::Created().json(product_result))
 }
 
-pub async fn get_products(pool: web::Data<PgPool>) -> impl Responder {
-    info!("Fetching all products");
-    match sqlx::query_as::<_, Product>("SELECT * FROM products")
-        .fetch_all(pool.get_ref())
-        .await
-    {
-        Ok(products) => {
-            info!("Found {} products", products.len());
-            HttpResponse::Ok().json(products)
+// The first id becomes the primary category
+async fn insert_product_categories(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    category_ids: &[Uuid],
+) -> Result<(), sqlx::Error> {
+    if category_ids.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO product_categories (product_id, category_id, is_primary)
+        SELECT $1, category_id, ordinality = 1
+        FROM UNNEST($2::uuid[]) WITH ORDINALITY AS c(category_id, ordinality)
+        "#,
+    )
+    .bind(product_id)
+    .bind(category_ids)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_products(
+    filters: web::Query<ProductFilters>,
+    params: web::Query<ListParams>,
+    facet_params: web::Query<FacetParams>,
+    viewer: Option<AuthenticatedVendor>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Fetching products with filters: {:?}", filters);
+    let mut filters = filters.into_inner();
+    restrict_to_visible(&mut filters, viewer.as_ref())?;
+    let tag_ids = parse_tag_ids(&filters)?;
+    let page = fetch_product_page(pool.get_ref(), &filters, &tag_ids, &params).await?;
+    info!("Found {} of {} products", page.items.len(), page.total);
+    if facet_params.facets {
+        let facets = fetch_facets(pool.get_ref(), None, &filters, &tag_ids).await?;
+        return Ok(HttpResponse::Ok().json(FacetedPage { page, facets }));
+    }
+    Ok(HttpResponse::Ok().json(page))
+}
+
+// Only live products are public. Vendors see every status when listing their own products,
+// moderators see everything.
+pub(crate) fn restrict_to_visible(
+    filters: &mut ProductFilters,
+    viewer: Option<&AuthenticatedVendor>,
+) -> Result<(), ApiError> {
+    let privileged =
+        viewer.is_some_and(|v| v.role == Role::Moderator || filters.vendor_id == Some(v.vendor_id));
+    if privileged {
+        return Ok(());
+    }
+    match filters.status {
+        None | Some(ProductStatus::Live) => {
+            filters.status = Some(ProductStatus::Live);
+            Ok(())
         }
-        Err(e) => {
-            error!("Failed to fetch products: {}", e);
-            HttpResponse::InternalServerError().json(format!("Error: {}", e))
+        Some(status) => Err(ApiError::forbidden(format!(
+            "Only the owning vendor can list {} products",
+            status.as_str()
+        ))),
+    }
+}
+
+fn can_view(product: &Product, viewer: Option<&AuthenticatedVendor>) -> bool {
+    product.status == ProductStatus::Live
+        || viewer.is_some_and(|v| v.role == Role::Moderator || v.vendor_id == product.vendor_id)
+}
+
+pub(crate) fn parse_tag_ids(filters: &ProductFilters) -> Result<Vec<Uuid>, ApiError> {
+    filters
+        .parsed_tag_ids()
+        .map_err(|e| ApiError::bad_request(format!("Invalid tag_ids: {}", e)))
+}
+
+pub(crate) async fn fetch_product_page(
+    pool: &PgPool,
+    filters: &ProductFilters,
+    tag_ids: &[Uuid],
+    params: &ListParams,
+) -> Result<Page<Product>, sqlx::Error> {
+    let limit = params.limit();
+    let offset = params.offset();
+
+    let mut count_query =
+        QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM products p WHERE TRUE");
+    push_product_filters(&mut count_query, filters, tag_ids);
+    let total: i64 = count_query.build_query_scalar().fetch_one(pool).await?;
+
+    let mut query = QueryBuilder::<Postgres>::new("SELECT p.* FROM products p WHERE TRUE");
+    push_product_filters(&mut query, filters, tag_ids);
+    let direction = params.order.unwrap_or_default().keyword();
+    query.push(format_args!(
+        " ORDER BY {} {}, p.id {}",
+        params.sort_by.unwrap_or_default().column(),
+        direction,
+        direction
+    ));
+    query.push(" LIMIT ").push_bind(limit);
+    query.push(" OFFSET ").push_bind(offset);
+    let items = query.build_query_as::<Product>().fetch_all(pool).await?;
+
+    Ok(Page {
+        items,
+        total,
+        limit,
+        offset,
+    })
+}
+
+pub async fn search_products(
+    search: web::Query<SearchParams>,
+    filters: web::Query<ProductFilters>,
+    params: web::Query<ListParams>,
+    facet_params: web::Query<FacetParams>,
+    viewer: Option<AuthenticatedVendor>,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let text = search.q.trim();
+    if text.is_empty() {
+        return Err(ApiError::bad_request("Search query `q` must not be empty"));
+    }
+    info!(
+        "Searching products for {:?} with filters: {:?}",
+        text, filters
+    );
+    let terms = SearchTerms { text, locale };
+    let mut filters = filters.into_inner();
+    restrict_to_visible(&mut filters, viewer.as_ref())?;
+    let tag_ids = parse_tag_ids(&filters)?;
+    let page = search_product_page(pool.get_ref(), terms, &filters, &tag_ids, &params).await?;
+    if facet_params.facets {
+        let facets = fetch_facets(pool.get_ref(), Some(terms), &filters, &tag_ids).await?;
+        return Ok(HttpResponse::Ok().json(FacetedPage { page, facets }));
+    }
+    Ok(HttpResponse::Ok().json(page))
+}
+
+// Full-text terms and the language to stem them in
+#[derive(Debug, Clone, Copy)]
+struct SearchTerms<'a> {
+    text: &'a str,
+    locale: Locale,
+}
+
+// Pushes `websearch_to_tsquery(config, text)`
+fn push_tsquery<'a>(query: &mut QueryBuilder<'a, Postgres>, terms: SearchTerms<'a>) {
+    query
+        .push("websearch_to_tsquery(")
+        .push_bind(terms.locale.text_search_config())
+        .push("::regconfig, ")
+        .push_bind(terms.text)
+        .push(")");
+}
+
+async fn search_product_page(
+    pool: &PgPool,
+    terms: SearchTerms<'_>,
+    filters: &ProductFilters,
+    tag_ids: &[Uuid],
+    params: &ListParams,
+) -> Result<Page<ProductSearchHit>, sqlx::Error> {
+    let limit = params.limit();
+    let offset = params.offset();
+
+    let mut count_query =
+        QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM products p WHERE p.search_vector @@ ");
+    push_tsquery(&mut count_query, terms);
+    push_product_filters(&mut count_query, filters, tag_ids);
+    let total: i64 = count_query.build_query_scalar().fetch_one(pool).await?;
+
+    let mut query = QueryBuilder::<Postgres>::new(
+        r#"
+        SELECT p.*,
+            ts_rank(p.search_vector, q.query) AS rank,
+            ts_headline("#,
+    );
+    query.push_bind(terms.locale.text_search_config());
+    query.push(
+        r#"::regconfig, p.description, q.query,
+                'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30') AS snippet
+        FROM products p, "#,
+    );
+    push_tsquery(&mut query, terms);
+    query.push(" AS q(query) WHERE p.search_vector @@ q.query");
+    push_product_filters(&mut query, filters, tag_ids);
+    // Relevance first unless the caller asked for an explicit ordering
+    match params.sort_by {
+        Some(sort_by) => {
+            let direction = params.order.unwrap_or_default().keyword();
+            query.push(format_args!(
+                " ORDER BY {} {}, p.id {}",
+                sort_by.column(),
+                direction,
+                direction
+            ));
+        }
+        None => {
+            query.push(" ORDER BY rank DESC, p.created_at DESC, p.id DESC");
+        }
+    }
+    query.push(" LIMIT ").push_bind(limit);
+    query.push(" OFFSET ").push_bind(offset);
+    let items = query
+        .build_query_as::<ProductSearchHit>()
+        .fetch_all(pool)
+        .await?;
+
+    Ok(Page {
+        items,
+        total,
+        limit,
+        offset,
+    })
+}
+
+// One count query per facet, each over the same filter set as the page itself
+async fn fetch_facets(
+    pool: &PgPool,
+    terms: Option<SearchTerms<'_>>,
+    filters: &ProductFilters,
+    tag_ids: &[Uuid],
+) -> Result<Facets, sqlx::Error> {
+    let categories = fetch_facet_values(
+        pool,
+        terms,
+        filters,
+        tag_ids,
+        "SELECT c.id, c.name, COUNT(*) AS count FROM matched m \
+         JOIN product_categories pc ON pc.product_id = m.id \
+         JOIN categories c ON c.id = pc.category_id GROUP BY c.id, c.name",
+    )
+    .await?;
+    let tags = fetch_facet_values(
+        pool,
+        terms,
+        filters,
+        tag_ids,
+        "SELECT t.id, t.name, COUNT(*) AS count FROM matched m \
+         JOIN product_tags pt ON pt.product_id = m.id \
+         JOIN tags t ON t.id = pt.tag_id GROUP BY t.id, t.name",
+    )
+    .await?;
+    let vendors = fetch_facet_values(
+        pool,
+        terms,
+        filters,
+        tag_ids,
+        "SELECT v.id, v.name, COUNT(*) AS count FROM matched m \
+         JOIN vendors v ON v.id = m.vendor_id GROUP BY v.id, v.name",
+    )
+    .await?;
+
+    let mut query = QueryBuilder::<Postgres>::new("");
+    push_matched_products(&mut query, terms, filters, tag_ids);
+    query.push("SELECT m.currency, width_bucket(m.price, CASE m.currency");
+    for currency in Currency::ALL {
+        query
+            .push(" WHEN ")
+            .push_bind(currency)
+            .push(" THEN ")
+            .push_bind(currency.price_bucket_edges().to_vec());
+    }
+    query.push(" END) AS bucket, COUNT(*) FROM matched m GROUP BY m.currency, bucket");
+    let counts: Vec<(Currency, i32, i64)> = query.build_query_as().fetch_all(pool).await?;
+    // Every bucket is listed, empty ones included, so the UI can render a stable set of ranges
+    let price = Currency::ALL
+        .into_iter()
+        .filter(|currency| counts.iter().any(|(c, _, _)| c == currency))
+        .map(|currency| {
+            let edges = currency.price_bucket_edges();
+            let buckets = edges
+                .iter()
+                .enumerate()
+                .map(|(i, min)| PriceBucket {
+                    min: *min,
+                    max: edges.get(i + 1).copied(),
+                    count: counts
+                        .iter()
+                        .find(|(c, bucket, _)| *c == currency && *bucket as usize == i + 1)
+                        .map_or(0, |(_, _, count)| *count),
+                })
+                .collect();
+            PriceFacet { currency, buckets }
+        })
+        .collect();
+
+    Ok(Facets {
+        categories,
+        tags,
+        vendors,
+        price,
+    })
+}
+
+// `select` reads from the `matched` CTE and yields `id, name, count` rows
+async fn fetch_facet_values(
+    pool: &PgPool,
+    terms: Option<SearchTerms<'_>>,
+    filters: &ProductFilters,
+    tag_ids: &[Uuid],
+    select: &str,
+) -> Result<Vec<FacetValue>, sqlx::Error> {
+    let mut query = QueryBuilder::<Postgres>::new("");
+    push_matched_products(&mut query, terms, filters, tag_ids);
+    query
+        .push(select)
+        .push(" ORDER BY count DESC, name LIMIT ")
+        .push_bind(MAX_FACET_VALUES);
+    query.build_query_as::<FacetValue>().fetch_all(pool).await
+}
+
+// Opens the query with a `matched` CTE holding the products that the listing or search would return
+fn push_matched_products<'a>(
+    query: &mut QueryBuilder<'a, Postgres>,
+    terms: Option<SearchTerms<'a>>,
+    filters: &ProductFilters,
+    tag_ids: &[Uuid],
+) {
+    query.push("WITH matched AS (SELECT p.* FROM products p WHERE ");
+    match terms {
+        Some(terms) => {
+            query.push("p.search_vector @@ ");
+            push_tsquery(query, terms);
+        }
+        None => {
+            query.push("TRUE");
+        }
+    }
+    push_product_filters(query, filters, tag_ids);
+    query.push(") ");
+}
+
+// Appends one `AND ...` clause per filter that is set; expects the products table aliased as `p`
+pub(crate) fn push_product_filters(
+    query: &mut QueryBuilder<'_, Postgres>,
+    filters: &ProductFilters,
+    tag_ids: &[Uuid],
+) {
+    if let Some(vendor_id) = filters.vendor_id {
+        query.push(" AND p.vendor_id = ").push_bind(vendor_id);
+    }
+    if let Some(category_id) = filters.category_id {
+        if filters.include_descendants {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM product_categories pc WHERE pc.product_id = p.id AND pc.category_id IN (")
+                .push("WITH RECURSIVE subtree AS (SELECT id FROM categories WHERE id = ")
+                .push_bind(category_id)
+                .push(" UNION ALL SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id)")
+                .push(" SELECT id FROM subtree))");
+        } else {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM product_categories pc WHERE pc.product_id = p.id AND pc.category_id = ")
+                .push_bind(category_id)
+                .push(")");
+        }
+    }
+    if !tag_ids.is_empty() {
+        match filters.tag_match.unwrap_or_default() {
+            TagMatch::Any => {
+                query
+                    .push(" AND EXISTS (SELECT 1 FROM product_tags pt WHERE pt.product_id = p.id AND pt.tag_id = ANY(")
+                    .push_bind(tag_ids.to_vec())
+                    .push("))");
+            }
+            TagMatch::All => {
+                query
+                    .push(" AND (SELECT COUNT(DISTINCT pt.tag_id) FROM product_tags pt WHERE pt.product_id = p.id AND pt.tag_id = ANY(")
+                    .push_bind(tag_ids.to_vec())
+                    .push(")) = ")
+                    .push_bind(tag_ids.len() as i64);
+            }
         }
     }
+    if let Some(status) = filters.status {
+        query.push(" AND p.status = ").push_bind(status);
+    }
+    if let Some(currency) = filters.currency {
+        query.push(" AND p.currency = ").push_bind(currency);
+    }
+    if let Some(min_price) = filters.min_price {
+        query.push(" AND p.price >= ").push_bind(min_price);
+    }
+    if let Some(max_price) = filters.max_price {
+        query.push(" AND p.price <= ").push_bind(max_price);
+    }
+    if let Some(created_after) = filters.created_after {
+        query.push(" AND p.created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = filters.created_before {
+        query
+            .push(" AND p.created_at <= ")
+            .push_bind(created_before);
+    }
+    if let Some(updated_after) = filters.updated_after {
+        query.push(" AND p.updated_at >= ").push_bind(updated_after);
+    }
+    if let Some(updated_before) = filters.updated_before {
+        query
+            .push(" AND p.updated_at <= ")
+            .push_bind(updated_before);
+    }
+}
+
+// Unpublished products are a 404 to everyone but their vendor and moderators
+pub async fn get_product(
+    product_id: web::Path<Uuid>,
+    viewer: Option<AuthenticatedVendor>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let detail = fetch_product_detail(pool.get_ref(), product_id.into_inner())
+        .await?
+        .filter(|detail| can_view(&detail.product, viewer.as_ref()))
+        .ok_or_else(|| ApiError::not_found("Product not found"))?;
+    Ok(HttpResponse::Ok().json(detail))
+}
+
+pub(crate) async fn fetch_product_detail(
+    pool: &PgPool,
+    product_id: Uuid,
+) -> Result<Option<ProductDetail>, sqlx::Error> {
+    let product = match sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_optional(pool)
+        .await?
+    {
+        Some(product) => product,
+        None => return Ok(None),
+    };
+    let categories = sqlx::query_as::<_, Category>(
+        r#"
+        SELECT c.* FROM categories c
+        JOIN product_categories pc ON pc.category_id = c.id
+        WHERE pc.product_id = $1
+        ORDER BY pc.is_primary DESC, c.name
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+    let tags = sqlx::query_as::<_, Tag>(
+        r#"
+        SELECT t.* FROM tags t
+        JOIN product_tags pt ON pt.tag_id = t.id
+        WHERE pt.product_id = $1
+        ORDER BY t.name
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+    let vendor = sqlx::query_as::<_, VendorSummary>("SELECT id, name FROM vendors WHERE id = $1")
+        .bind(product.vendor_id)
+        .fetch_one(pool)
+        .await?;
+    // Products created before primaries existed may lack one; the first by name stands in
+    let category = categories.first().cloned();
+    Ok(Some(ProductDetail {
+        product,
+        category,
+        categories,
+        tags,
+        vendor,
+    }))
 }
 
 pub async fn update_product(
     product_id: web::Path<Uuid>,
     product: Json<NewProduct>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
     pool: web::Data<PgPool>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let product_id = product_id.into_inner();
+    auth.ensure_product_owner(pool.get_ref(), product_id)
+        .await?;
     let product = product.into_inner();
-    let mut tx = match pool.begin().await {
-        Ok(tx) => tx,
-        Err(e) => return HttpResponse::InternalServerError().json(format!("Error: {}", e)),
-    };
+    validate_product(pool.get_ref(), &product, locale).await?;
+    let category_ids = product.all_category_ids();
+    let mut tx = pool.begin().await?;
+    // Submitted and published products are frozen; the vendor has to move them back to draft first
+    let status = sqlx::query_scalar::<_, ProductStatus>(
+        "SELECT status FROM products WHERE id = $1 FOR UPDATE",
+    )
+    .bind(product_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !status.is_editable() {
+        return Err(ApiError::Conflict {
+            message: format!("Products cannot be edited while {}", status.as_str()),
+            details: Some(json!({ "status": status })),
+        });
+    }
     // Update the product
-    let update_result = sqlx::query(
+    let updated = sqlx::query_as::<_, Product>(
         r#"
         UPDATE products
-        SET vendor_id = $1, name = $2, description = $3, price = $4, image_url = $5, is_draft = $6, updated_at = NOW()
-        WHERE id = $7
-        "#
+        SET name = $1, description = $2, price = $3, currency = $4, image_url = $5,
+            updated_at = NOW()
+        WHERE id = $6
+        RETURNING *
+        "#,
     )
-    .bind(product.vendor_id)
     .bind(product.name)
     .bind(product.description)
     .bind(product.price)
+    .bind(product.currency)
     .bind(product.image_url)
-    .bind(product.is_draft)
     .bind(product_id)
-    .execute(&mut *tx)
-    .await;
-    if let Err(e) = update_result {
-        let _ = tx.rollback().await;
-        return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-    }
-    // Update category (remove old, add new if provided)
-    let _ = sqlx::query("DELETE FROM product_categories WHERE product_id = $1")
-        .bind(product_id)
-        .execute(&mut *tx)
-        .await;
-    if let Some(category_id) = product.category_id {
-        let _ = sqlx::query(
-            r#"INSERT INTO product_categories (product_id, category_id) VALUES ($1, $2)"#,
-        )
+    .fetch_one(&mut *tx)
+    .await?;
+    // Update categories (remove old, add new if provided)
+    sqlx::query("DELETE FROM product_categories WHERE product_id = $1")
         .bind(product_id)
-        .bind(category_id)
         .execute(&mut *tx)
-        .await;
-    }
+        .await?;
+    insert_product_categories(&mut tx, product_id, &category_ids).await?;
     // Update tags (remove old, add new if provided)
-    let _ = sqlx::query("DELETE FROM product_tags WHERE product_id = $1")
+    sqlx::query("DELETE FROM product_tags WHERE product_id = $1")
         .bind(product_id)
         .execute(&mut *tx)
-        .await;
+        .await?;
     if let Some(tag_ids) = product.tag_ids {
         for tag_id in tag_ids {
-            let _ = sqlx::query(r#"INSERT INTO product_tags (product_id, tag_id) VALUES ($1, $2)"#)
+            sqlx::query(r#"INSERT INTO product_tags (product_id, tag_id) VALUES ($1, $2)"#)
                 .bind(product_id)
                 .bind(tag_id)
                 .execute(&mut *tx)
-                .await;
+                .await?;
         }
     }
-    if let Err(e) = tx.commit().await {
-        return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-    }
-    HttpResponse::Ok().json("Product updated successfully")
+    webhooks::emit(
+        &mut tx,
+        WebhookEvent::Updated,
+        updated.vendor_id,
+        &json!({ "product": updated }),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json("Product updated successfully"))
 }
 
 pub async fn delete_product(
     product_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
     pool: web::Data<PgPool>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let product_id = product_id.into_inner();
-    let mut tx = match pool.begin().await {
-        Ok(tx) => tx,
-        Err(e) => return HttpResponse::InternalServerError().json(format!("Error: {}", e)),
-    };
+    auth.ensure_product_owner(pool.get_ref(), product_id)
+        .await?;
+    let mut tx = pool.begin().await?;
     // Delete from product_categories
-    if let Err(e) = sqlx::query("DELETE FROM product_categories WHERE product_id = $1")
+    sqlx::query("DELETE FROM product_categories WHERE product_id = $1")
         .bind(product_id)
         .execute(&mut *tx)
-        .await
-    {
-        let _ = tx.rollback().await;
-        return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-    }
+        .await?;
     // Delete from product_tags
-    if let Err(e) = sqlx::query("DELETE FROM product_tags WHERE product_id = $1")
+    sqlx::query("DELETE FROM product_tags WHERE product_id = $1")
         .bind(product_id)
         .execute(&mut *tx)
-        .await
-    {
-        let _ = tx.rollback().await;
-        return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-    }
+        .await?;
+    // Delete from verification_reports
+    sqlx::query("DELETE FROM verification_reports WHERE product_id = $1")
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+    // Delete from product_status_history
+    sqlx::query("DELETE FROM product_status_history WHERE product_id = $1")
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
     // Delete from notifications
-    if let Err(e) = sqlx::query("DELETE FROM notifications WHERE product_id = $1")
+    sqlx::query("DELETE FROM notifications WHERE product_id = $1")
         .bind(product_id)
         .execute(&mut *tx)
-        .await
-    {
-        let _ = tx.rollback().await;
-        return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-    }
+        .await?;
     // Delete the product
-    let result = sqlx::query("DELETE FROM products WHERE id = $1")
+    let deleted = sqlx::query_as::<_, Product>("DELETE FROM products WHERE id = $1 RETURNING *")
         .bind(product_id)
-        .execute(&mut *tx)
-        .await;
-    match result {
-        Ok(res) => {
-            if res.rows_affected() == 0 {
-                let _ = tx.rollback().await;
-                HttpResponse::NotFound().json("Product not found")
-            } else {
-                let _ = tx.commit().await;
-                HttpResponse::Ok().json("Product deleted successfully")
-            }
-        }
-        Err(e) => {
-            let _ = tx.rollback().await;
-            HttpResponse::InternalServerError().json(format!("Error: {}", e))
-        }
-    }
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Product not found"))?;
+    webhooks::emit(
+        &mut tx,
+        WebhookEvent::Deleted,
+        deleted.vendor_id,
+        &json!({ "product": deleted }),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json("Product deleted successfully"))
 }
 
-// Draft and submit
-pub async fn save_draft(product_id: web::Path<Uuid>, pool: web::Data<PgPool>) -> impl Responder {
-    let product_id = product_id.into_inner();
-    match sqlx::query(
-        r#"
-        UPDATE products
-        SET is_draft = true
-        WHERE id = $1
-        "#,
+// Lifecycle
+pub async fn save_draft(
+    product_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let transition = vendor_transition(
+        pool.get_ref(),
+        &auth,
+        product_id.into_inner(),
+        ProductStatus::Draft,
     )
-    .bind(product_id)
-    .execute(pool.get_ref())
-    .await
-    {
-        Ok(_) => HttpResponse::Ok().json("Draft saved successfully"),
-        Err(e) => HttpResponse::InternalServerError().json(format!("Error: {}", e)),
-    }
+    .await?;
+    Ok(HttpResponse::Ok().json(transition))
 }
 
 pub async fn submit_product(
     product_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
     pool: web::Data<PgPool>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let product_id = product_id.into_inner();
-    // Set is_verified to NULL (pending)
-    let _ =
-        sqlx::query(r#"UPDATE products SET is_draft = false, is_verified = NULL WHERE id = $1"#)
-            .bind(product_id)
-            .execute(pool.get_ref())
-            .await;
-    // Trigger verification process
-    verify_product(product_id, pool).await;
-    HttpResponse::Ok().json("Product submitted successfully")
+    auth.ensure_product_owner(pool.get_ref(), product_id)
+        .await?;
+    let mut tx = pool.begin().await?;
+    lifecycle::transition(
+        &mut tx,
+        product_id,
+        ProductStatus::Submitted,
+        Some(auth.vendor_id),
+        None,
+    )
+    .await?;
+    // Queued in the same transaction so a submitted product always has a verification pending
+    let job = jobs::enqueue(
+        &mut tx,
+        &Task::VerifyProduct { product_id },
+        Some(auth.vendor_id),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Accepted()
+        .insert_header(("Location", format!("/api/jobs/{}", job.id)))
+        .json(job))
 }
 
-async fn verify_product(product_id: Uuid, pool: web::Data<PgPool>) {
-    // Fetch product and related info
-    let product = sqlx::query!(
-        r#"
-        SELECT p.name, p.description, p.price, p.image_url,
-            (SELECT COUNT(*) FROM product_categories WHERE product_id = p.id) as category_count,
-            (SELECT COUNT(*) FROM product_tags WHERE product_id = p.id) as tag_count,
-            p.vendor_id
-        FROM products p
-        WHERE p.id = $1
-        "#,
-        product_id
+pub async fn archive_product(
+    product_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let transition = vendor_transition(
+        pool.get_ref(),
+        &auth,
+        product_id.into_inner(),
+        ProductStatus::Archived,
     )
-    .fetch_one(pool.get_ref())
-    .await;
-
-    let (is_verified, message) = match product {
-        Ok(p) => {
-            let has_name = !p.name.trim().is_empty();
-            let has_desc = !p.description.trim().is_empty();
-            let has_image = !p.image_url.trim().is_empty();
-            let has_category = p.category_count.unwrap_or(0) > 0;
-            let has_tag = p.tag_count.unwrap_or(0) > 0;
-            let price_ok = p.price >= 500.0 && p.price <= 1_000_000_000.0;
-            if has_name && has_desc && has_image && has_category && has_tag && price_ok {
-                (true, "Your product has been verified and is now live!")
-            } else {
-                (
-                    false,
-                    "Your product verification failed. Please check the requirements.",
-                )
-            }
-        }
-        Err(_) => (
-            false,
-            "Your product verification failed. Please check the requirements.",
-        ),
-    };
-
-    // Update verification status
-    let _ = sqlx::query(r#"UPDATE products SET is_verified = $1 WHERE id = $2"#)
-        .bind(is_verified)
-        .bind(product_id)
-        .execute(pool.get_ref())
-        .await;
+    .await?;
+    Ok(HttpResponse::Ok().json(transition))
+}
 
-    // Create notification
-    match sqlx::query(
-        r#"
-        INSERT INTO notifications (vendor_id, product_id, message)
-        SELECT vendor_id, id, $1
-        FROM products
-        WHERE id = $2
-        "#
+// Archived products come back as drafts so they are verified again before going live
+pub async fn restore_product(
+    product_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let product_id = product_id.into_inner();
+    auth.ensure_product_owner(pool.get_ref(), product_id)
+        .await?;
+    let mut tx = pool.begin().await?;
+    let status = sqlx::query_scalar::<_, ProductStatus>(
+        "SELECT status FROM products WHERE id = $1 FOR UPDATE",
     )
-    .bind(message)
     .bind(product_id)
-    .execute(pool.get_ref())
-    .await {
-        Ok(_) => {},
-        Err(e) => error!("Failed to insert notification: {}", e),
+    .fetch_one(&mut *tx)
+    .await?;
+    if status != ProductStatus::Archived {
+        return Err(ApiError::conflict("Only archived products can be restored"));
+    }
+    let transition = lifecycle::transition(
+        &mut tx,
+        product_id,
+        ProductStatus::Draft,
+        Some(auth.vendor_id),
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(transition))
+}
+
+pub async fn get_product_history(
+    product_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let product_id = product_id.into_inner();
+    if auth.role != Role::Moderator {
+        auth.ensure_product_owner(pool.get_ref(), product_id)
+            .await?;
     }
+    let mut conn = pool.acquire().await?;
+    let history = lifecycle::history(&mut conn, product_id).await?;
+    Ok(HttpResponse::Ok().json(history))
+}
+
+async fn vendor_transition(
+    pool: &PgPool,
+    auth: &AuthenticatedVendor,
+    product_id: Uuid,
+    to: ProductStatus,
+) -> Result<StatusTransition, ApiError> {
+    auth.ensure_product_owner(pool, product_id).await?;
+    let mut tx = pool.begin().await?;
+    let transition =
+        lifecycle::transition(&mut tx, product_id, to, Some(auth.vendor_id), None).await?;
+    tx.commit().await?;
+    Ok(transition)
+}
+
+pub async fn get_verification_report(
+    product_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let product_id = product_id.into_inner();
+    auth.ensure_product_owner(pool.get_ref(), product_id)
+        .await?;
+    let report = verification::latest_report(pool.get_ref(), product_id, locale)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Product has not been submitted for verification"))?;
+    Ok(HttpResponse::Ok().json(report))
 }
 
 // File upload
-pub async fn upload_file(mut payload: Multipart) -> impl Responder {
+// Any authenticated vendor may upload; ownership is enforced when the URL is attached to a product
+pub async fn upload_file(
+    _auth: AuthenticatedVendor,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ApiError> {
     let upload_dir = Path::new("uploads");
     if !upload_dir.exists() {
-        if let Err(e) = fs::create_dir_all(upload_dir) {
-            error!("Failed to create upload directory: {}", e);
-            return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-        }
+        fs::create_dir_all(upload_dir)
+            .map_err(|e| ApiError::internal(format!("Failed to create upload directory: {}", e)))?;
     }
     while let Some(item) = payload.next().await {
-        let mut field = match item {
-            Ok(field) => field,
-            Err(e) => {
-                error!("Error in multipart field: {}", e);
-                return HttpResponse::BadRequest().json(format!("Error: {}", e));
-            }
-        };
-        let content_type = field.content_type().map(|ct| ct.clone());
+        let mut field =
+            item.map_err(|e| ApiError::bad_request(format!("Invalid multipart field: {}", e)))?;
+        let content_type = field.content_type().cloned();
         if let Some(ct) = content_type {
             if ct.type_() == "image" {
                 let ext = ct.subtype().as_str();
@@ -362,13 +832,9 @@ pub async fn upload_file(mut payload: Multipart) -> impl Responder {
                 // Read the image into memory
                 let mut bytes = Vec::new();
                 while let Some(chunk) = field.next().await {
-                    let data = match chunk {
-                        Ok(data) => data,
-                        Err(e) => {
-                            error!("Error reading chunk: {}", e);
-                            return HttpResponse::BadRequest().json(format!("Error: {}", e));
-                        }
-                    };
+                    let data = chunk.map_err(|e| {
+                        ApiError::bad_request(format!("Error reading upload: {}", e))
+                    })?;
                     bytes.extend_from_slice(&data);
                 }
                 // Try to optimize JPEG and PNG
@@ -406,24 +872,64 @@ pub async fn upload_file(mut payload: Multipart) -> impl Responder {
                 };
                 // Save the optimized or original image
                 let to_save = optimized.as_ref().unwrap_or(&bytes);
-                match fs::write(&filepath, to_save) {
-                    Ok(_) => {
-                        let image_url = format!("/uploads/{}", filename);
-                        return HttpResponse::Ok().json(serde_json::json!({
-                            "success": true,
-                            "image_url": image_url
-                        }));
-                    }
-                    Err(e) => {
-                        error!("Failed to save file: {}", e);
-                        return HttpResponse::InternalServerError().json(format!("Error: {}", e));
-                    }
-                }
+                fs::write(&filepath, to_save)
+                    .map_err(|e| ApiError::internal(format!("Failed to save file: {}", e)))?;
+                let image_url = format!("/uploads/{}", filename);
+                return Ok(HttpResponse::Ok().json(serde_json::json!({
+                    "success": true,
+                    "image_url": image_url
+                })));
             }
         }
     }
-    HttpResponse::BadRequest().json(serde_json::json!({
-        "success": false,
-        "error": "No image file found in the request"
-    }))
+    Err(ApiError::bad_request("No image file found in the request"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn search_stems_words_in_both_languages(pool: PgPool) {
+        let vendor_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO vendors (name, email) VALUES ('Pagne Couture', 'pagne@example.com') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO products (vendor_id, name, description, price, image_url, status)
+            VALUES ($1, 'Wax print gown', 'Long dresses in wax fabric', 20000, '', 'live'),
+                ($1, 'Tenue en pagne', 'Robes longues cousues main', 18000, '', 'live')
+            "#,
+        )
+        .bind(vendor_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let names = |text: &'static str, locale: Locale| {
+            let pool = pool.clone();
+            async move {
+                let terms = SearchTerms { text, locale };
+                search_product_page(
+                    &pool,
+                    terms,
+                    &ProductFilters::default(),
+                    &[],
+                    &ListParams::default(),
+                )
+                .await
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|hit| hit.product.name)
+                .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(names("dress", Locale::En).await, ["Wax print gown"]);
+        assert_eq!(names("robe", Locale::Fr).await, ["Tenue en pagne"]);
+        assert_eq!(names("cousu", Locale::Fr).await, ["Tenue en pagne"]);
+    }
 }
@@ -1,13 +1,316 @@
+use crate::auth::{AuthenticatedVendor, Moderator};
+use crate::errors::ApiError;
+use crate::i18n::Locale;
 use crate::models::*;
-use actix_web::{web, HttpResponse, Responder};
+use crate::validation::{Validate, ValidationErrors};
+use actix_web::{web, HttpResponse};
+use log::info;
 use sqlx::PgPool;
+use uuid::Uuid;
 
-pub async fn get_tags(pool: web::Data<PgPool>) -> impl Responder {
-    match sqlx::query_as::<_, Tag>("SELECT * FROM tags")
+const TAG_NAME_TAKEN: &str = "A tag with this name already exists";
+
+fn normalize_tag(tag: NewTag, locale: Locale) -> Result<NewTag, ApiError> {
+    let mut errors = ValidationErrors::new(locale);
+    tag.validate(&mut errors);
+    errors.into_result()?;
+    Ok(NewTag {
+        name: tag.name.trim().to_string(),
+    })
+}
+
+// Only approved tags; proposals are listed separately
+pub async fn get_tags(
+    params: web::Query<CountParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    if params.with_counts {
+        let tags = sqlx::query_as::<_, TagWithCounts>(
+            r#"
+            SELECT t.*,
+                COUNT(p.id) FILTER (WHERE p.status = 'live') AS live,
+                COUNT(p.id) FILTER (WHERE p.status = 'draft') AS draft,
+                COUNT(p.id) FILTER (WHERE p.status = 'rejected') AS rejected,
+                COUNT(p.id) AS total
+            FROM tags t
+            LEFT JOIN product_tags pt ON pt.tag_id = t.id
+            LEFT JOIN products p ON p.id = pt.product_id
+            WHERE t.status = 'approved'
+            GROUP BY t.id
+            ORDER BY t.name
+            "#,
+        )
         .fetch_all(pool.get_ref())
+        .await?;
+        return Ok(HttpResponse::Ok().json(tags));
+    }
+    let tags =
+        sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE status = 'approved' ORDER BY name")
+            .fetch_all(pool.get_ref())
+            .await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+// Tags on live products that went live within the last `days` days, most used first
+pub async fn get_popular_tags(
+    params: web::Query<PopularTagsParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tags = sqlx::query_as::<_, PopularTag>(
+        r#"
+        SELECT t.*, COUNT(*) AS product_count
+        FROM tags t
+        JOIN product_tags pt ON pt.tag_id = t.id
+        JOIN products p ON p.id = pt.product_id
+        WHERE t.status = 'approved'
+            AND p.status = 'live'
+            AND EXISTS (
+                SELECT 1 FROM product_status_history h
+                WHERE h.product_id = p.id
+                    AND h.to_status = 'live'
+                    AND h.created_at >= NOW() - make_interval(days => $1::int)
+            )
+        GROUP BY t.id
+        ORDER BY product_count DESC, t.name
+        LIMIT $2
+        "#,
+    )
+    .bind(params.days())
+    .bind(params.limit())
+    .fetch_all(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+async fn insert_tag(
+    pool: &PgPool,
+    tag: NewTag,
+    status: TagStatus,
+    proposed_by: Option<Uuid>,
+) -> Result<Tag, ApiError> {
+    sqlx::query_as::<_, Tag>(
+        r#"
+        INSERT INTO tags (name, status, proposed_by)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(tag.name)
+    .bind(status)
+    .bind(proposed_by)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| ApiError::from(e).on_conflict(TAG_NAME_TAKEN))
+}
+
+pub async fn create_tag(
+    tag: web::Json<NewTag>,
+    _moderator: Moderator,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tag = normalize_tag(tag.into_inner(), locale)?;
+    let tag = insert_tag(pool.get_ref(), tag, TagStatus::Approved, None).await?;
+    info!("Created tag: {:?}", tag);
+    Ok(HttpResponse::Created().json(tag))
+}
+
+// Any vendor may suggest a tag; it becomes usable once a moderator approves it
+pub async fn propose_tag(
+    tag: web::Json<NewTag>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tag = normalize_tag(tag.into_inner(), locale)?;
+    let tag = insert_tag(
+        pool.get_ref(),
+        tag,
+        TagStatus::Pending,
+        Some(auth.vendor_id),
+    )
+    .await?;
+    info!("Vendor {} proposed tag: {:?}", auth.vendor_id, tag);
+    Ok(HttpResponse::Created().json(tag))
+}
+
+// The caller's own proposals that are still waiting for a decision
+pub async fn get_tag_proposals(
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tags = sqlx::query_as::<_, Tag>(
+        r#"
+        SELECT * FROM tags
+        WHERE proposed_by = $1 AND status = 'pending'
+        ORDER BY created_at DESC, id DESC
+        "#,
+    )
+    .bind(auth.vendor_id)
+    .fetch_all(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+// Oldest first, like the product moderation queue
+pub async fn get_tag_queue(
+    _moderator: Moderator,
+    params: web::Query<ListParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = params.limit();
+    let offset = params.offset();
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM tags WHERE status = 'pending'")
+        .fetch_one(pool.get_ref())
+        .await?;
+    let items = sqlx::query_as::<_, Tag>(
+        r#"
+        SELECT * FROM tags
+        WHERE status = 'pending'
+        ORDER BY created_at ASC, id ASC
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(Page {
+        items,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+pub async fn approve_tag(
+    tag_id: web::Path<Uuid>,
+    _moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tag = sqlx::query_as::<_, Tag>(
+        "UPDATE tags SET status = 'approved' WHERE id = $1 AND status = 'pending' RETURNING *",
+    )
+    .bind(tag_id.into_inner())
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Tag proposal not found"))?;
+    Ok(HttpResponse::Ok().json(tag))
+}
+
+// Rejected proposals are dropped so the name can be proposed again later
+pub async fn reject_tag(
+    tag_id: web::Path<Uuid>,
+    _moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let result = sqlx::query("DELETE FROM tags WHERE id = $1 AND status = 'pending'")
+        .bind(tag_id.into_inner())
+        .execute(pool.get_ref())
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Tag proposal not found"));
+    }
+    Ok(HttpResponse::Ok().json("Tag proposal rejected"))
+}
+
+pub async fn rename_tag(
+    tag_id: web::Path<Uuid>,
+    tag: web::Json<NewTag>,
+    _moderator: Moderator,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tag = normalize_tag(tag.into_inner(), locale)?;
+    let tag = sqlx::query_as::<_, Tag>("UPDATE tags SET name = $1 WHERE id = $2 RETURNING *")
+        .bind(tag.name)
+        .bind(tag_id.into_inner())
+        .fetch_optional(pool.get_ref())
         .await
-    {
-        Ok(tags) => HttpResponse::Ok().json(tags),
-        Err(e) => HttpResponse::InternalServerError().json(format!("Error: {}", e)),
+        .map_err(|e| ApiError::from(e).on_conflict(TAG_NAME_TAKEN))?
+        .ok_or_else(|| ApiError::not_found("Tag not found"))?;
+    Ok(HttpResponse::Ok().json(tag))
+}
+
+// Products tagged with the source get the target instead. The source may be a pending
+// proposal, which is how a duplicate suggestion is folded into an existing tag.
+pub async fn merge_tag(
+    tag_id: web::Path<Uuid>,
+    request: web::Json<MergeRequest>,
+    _moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let source_id = tag_id.into_inner();
+    let target_id = request.into;
+    if source_id == target_id {
+        return Err(ApiError::bad_request("A tag cannot be merged into itself"));
+    }
+    let mut tx = pool.begin().await?;
+    // Locked in a fixed order so two opposite merges cannot deadlock
+    let locked =
+        sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+            .bind([source_id, target_id])
+            .fetch_all(&mut *tx)
+            .await?;
+    if !locked.iter().any(|t| t.id == source_id) {
+        return Err(ApiError::not_found("Tag not found"));
+    }
+    let target = locked
+        .into_iter()
+        .find(|t| t.id == target_id)
+        .ok_or_else(|| ApiError::not_found("Target tag not found"))?;
+    if target.status != TagStatus::Approved {
+        return Err(ApiError::conflict(
+            "Tags can only be merged into an approved tag",
+        ));
+    }
+    let moved = sqlx::query(
+        r#"
+        INSERT INTO product_tags (product_id, tag_id)
+        SELECT product_id, $2 FROM product_tags WHERE tag_id = $1
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(source_id)
+    .bind(target_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    sqlx::query("DELETE FROM product_tags WHERE tag_id = $1")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM tags WHERE id = $1")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    info!(
+        "Merged tag {} into {} ({} products moved)",
+        source_id, target_id, moved
+    );
+    Ok(HttpResponse::Ok().json(target))
+}
+
+// Unlike categories, a tag is optional on a product, so deleting it just detaches it everywhere
+pub async fn delete_tag(
+    tag_id: web::Path<Uuid>,
+    _moderator: Moderator,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tag_id = tag_id.into_inner();
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM product_tags WHERE tag_id = $1")
+        .bind(tag_id)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query("DELETE FROM tags WHERE id = $1")
+        .bind(tag_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Tag not found"));
     }
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json("Tag deleted successfully"))
 }
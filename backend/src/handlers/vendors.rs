@@ -0,0 +1,219 @@
+use crate::auth::{hash_password, verify_password, AuthenticatedVendor};
+use crate::errors::ApiError;
+use crate::handlers::products::{fetch_product_page, parse_tag_ids, restrict_to_visible};
+use crate::i18n::{Locale, Message};
+use crate::models::*;
+use crate::validation::{Validate, ValidationErrors};
+use actix_web::{web, HttpResponse};
+use log::info;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const EMAIL_TAKEN: &str = "A vendor with this email already exists";
+
+// Trims the name and normalizes the email so uniqueness is case-insensitive
+fn normalize_vendor(vendor: NewVendor, locale: Locale) -> Result<NewVendor, ApiError> {
+    let mut errors = ValidationErrors::new(locale);
+    vendor.validate(&mut errors);
+    errors.into_result()?;
+    Ok(NewVendor {
+        name: vendor.name.trim().to_string(),
+        email: vendor.email.trim().to_lowercase(),
+        password: vendor.password,
+        locale: vendor.locale,
+        current_password: vendor.current_password,
+    })
+}
+
+fn hash_optional_password(password: Option<&str>) -> Result<Option<String>, ApiError> {
+    password
+        .map(hash_password)
+        .transpose()
+        .map_err(|e| ApiError::internal(format!("Failed to hash password: {}", e)))
+}
+
+pub async fn create_vendor(
+    vendor: web::Json<NewVendor>,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor = normalize_vendor(vendor.into_inner(), locale)?;
+    if vendor.password.is_none() {
+        let mut errors = ValidationErrors::new(locale);
+        errors.add("password", Message::new("validation-password-required"));
+        return Err(errors.into());
+    }
+    let password_hash = hash_optional_password(vendor.password.as_deref())?;
+    let vendor = sqlx::query_as::<_, Vendor>(
+        r#"
+        INSERT INTO vendors (name, email, password_hash, locale)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(vendor.name)
+    .bind(vendor.email)
+    .bind(password_hash)
+    .bind(vendor.locale)
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::from(e).on_conflict(EMAIL_TAKEN))?;
+    info!("Registered vendor: {:?}", vendor);
+    Ok(HttpResponse::Created().json(vendor))
+}
+
+// Public directory: names only, never emails or roles
+pub async fn get_vendors(
+    params: web::Query<ListParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = params.limit();
+    let offset = params.offset();
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM vendors")
+        .fetch_one(pool.get_ref())
+        .await?;
+    let items = sqlx::query_as::<_, VendorSummary>(
+        r#"
+        SELECT id, name FROM vendors
+        ORDER BY created_at DESC, id DESC
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(Page {
+        items,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+// The full record is only shown to the vendor themselves; everyone else gets the public summary
+pub async fn get_vendor(
+    vendor_id: web::Path<Uuid>,
+    viewer: Option<AuthenticatedVendor>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor = sqlx::query_as::<_, Vendor>("SELECT * FROM vendors WHERE id = $1")
+        .bind(vendor_id.into_inner())
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::not_found("Vendor not found"))?;
+    if viewer.is_some_and(|v| v.vendor_id == vendor.id) {
+        return Ok(HttpResponse::Ok().json(vendor));
+    }
+    Ok(HttpResponse::Ok().json(VendorSummary {
+        id: vendor.id,
+        name: vendor.name,
+    }))
+}
+
+pub async fn update_vendor(
+    vendor_id: web::Path<Uuid>,
+    vendor: web::Json<NewVendor>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let vendor = normalize_vendor(vendor.into_inner(), locale)?;
+    let (current_email, current_hash) = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT email, password_hash FROM vendors WHERE id = $1",
+    )
+    .bind(vendor_id)
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Vendor not found"))?;
+    // A stolen token alone must not be enough to take over the account
+    if vendor.email != current_email || vendor.password.is_some() {
+        let confirmed = match (&vendor.current_password, &current_hash) {
+            (Some(password), Some(hash)) => verify_password(password, hash),
+            _ => false,
+        };
+        if !confirmed {
+            let mut errors = ValidationErrors::new(locale);
+            errors.add(
+                "current_password",
+                Message::new(if vendor.current_password.is_some() {
+                    "validation-current-password-wrong"
+                } else {
+                    "validation-current-password-required"
+                }),
+            );
+            return Err(errors.into());
+        }
+    }
+    let password_hash = hash_optional_password(vendor.password.as_deref())?;
+    let vendor = sqlx::query_as::<_, Vendor>(
+        r#"
+        UPDATE vendors
+        SET name = $1, email = $2, password_hash = COALESCE($3, password_hash),
+            locale = COALESCE($4, locale)
+        WHERE id = $5
+        RETURNING *
+        "#,
+    )
+    .bind(vendor.name)
+    .bind(vendor.email)
+    .bind(password_hash)
+    .bind(vendor.locale)
+    .bind(vendor_id)
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::from(e).on_conflict(EMAIL_TAKEN))?
+    .ok_or_else(|| ApiError::not_found("Vendor not found"))?;
+    Ok(HttpResponse::Ok().json(vendor))
+}
+
+pub async fn delete_vendor(
+    vendor_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    auth.ensure_vendor(vendor_id)?;
+    let result = sqlx::query("DELETE FROM vendors WHERE id = $1")
+        .bind(vendor_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| match ApiError::from(e) {
+            ApiError::Unprocessable { details, .. } => ApiError::Conflict {
+                message: "Vendor still has products or notifications and cannot be deleted"
+                    .to_string(),
+                details,
+            },
+            other => other,
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Vendor not found"));
+    }
+    Ok(HttpResponse::Ok().json("Vendor deleted successfully"))
+}
+
+pub async fn get_vendor_products(
+    vendor_id: web::Path<Uuid>,
+    filters: web::Query<ProductFilters>,
+    params: web::Query<ListParams>,
+    viewer: Option<AuthenticatedVendor>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let vendor_id = vendor_id.into_inner();
+    let exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM vendors WHERE id = $1)")
+            .bind(vendor_id)
+            .fetch_one(pool.get_ref())
+            .await?;
+    if !exists {
+        return Err(ApiError::not_found("Vendor not found"));
+    }
+    let mut filters = filters.into_inner();
+    filters.vendor_id = Some(vendor_id);
+    restrict_to_visible(&mut filters, viewer.as_ref())?;
+    let tag_ids = parse_tag_ids(&filters)?;
+    let page = fetch_product_page(pool.get_ref(), &filters, &tag_ids, &params).await?;
+    Ok(HttpResponse::Ok().json(page))
+}
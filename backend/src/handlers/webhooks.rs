@@ -0,0 +1,221 @@
+use crate::auth::{AuthenticatedVendor, Role};
+use crate::errors::ApiError;
+use crate::i18n::{Locale, Message};
+use crate::models::*;
+use crate::validation::{Validate, ValidationErrors};
+use crate::webhooks::{check_target, generate_secret};
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn validate_subscription(
+    subscription: &NewWebhookSubscription,
+    locale: Locale,
+) -> Result<(), ApiError> {
+    let mut errors = ValidationErrors::new(locale);
+    subscription.validate(&mut errors);
+    if errors.is_empty() {
+        if let Err(id) = check_target(&subscription.url).await {
+            errors.add("url", Message::new(id));
+        }
+    }
+    errors.into_result()
+}
+
+// Vendors manage their own subscriptions; global ones belong to moderators
+async fn load_subscription(
+    pool: &PgPool,
+    auth: &AuthenticatedVendor,
+    subscription_id: Uuid,
+) -> Result<WebhookSubscription, ApiError> {
+    let subscription = sqlx::query_as::<_, WebhookSubscription>(
+        "SELECT * FROM webhook_subscriptions WHERE id = $1",
+    )
+    .bind(subscription_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::not_found("Webhook subscription not found"))?;
+    let allowed = match subscription.vendor_id {
+        Some(vendor_id) => vendor_id == auth.vendor_id,
+        None => auth.role == Role::Moderator,
+    };
+    if allowed {
+        Ok(subscription)
+    } else {
+        Err(ApiError::forbidden(
+            "You do not have access to this webhook subscription",
+        ))
+    }
+}
+
+pub async fn create_webhook(
+    subscription: web::Json<NewWebhookSubscription>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let subscription = subscription.into_inner();
+    validate_subscription(&subscription, locale).await?;
+    if subscription.global && auth.role != Role::Moderator {
+        return Err(ApiError::forbidden(
+            "Only moderators can create global webhook subscriptions",
+        ));
+    }
+    let secret = subscription.secret.unwrap_or_else(generate_secret);
+    let created = sqlx::query_as::<_, WebhookSubscription>(
+        r#"
+        INSERT INTO webhook_subscriptions (vendor_id, url, secret, events, active)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind((!subscription.global).then_some(auth.vendor_id))
+    .bind(subscription.url.trim())
+    .bind(&secret)
+    .bind(&subscription.events)
+    .bind(subscription.active.unwrap_or(true))
+    .fetch_one(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Created().json(CreatedWebhookSubscription {
+        subscription: created,
+        secret,
+    }))
+}
+
+// Moderators also see the global subscriptions
+pub async fn get_webhooks(
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let subscriptions = sqlx::query_as::<_, WebhookSubscription>(
+        r#"
+        SELECT * FROM webhook_subscriptions
+        WHERE vendor_id = $1 OR ($2 AND vendor_id IS NULL)
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(auth.vendor_id)
+    .bind(auth.role == Role::Moderator)
+    .fetch_all(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(subscriptions))
+}
+
+pub async fn get_webhook(
+    subscription_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let subscription =
+        load_subscription(pool.get_ref(), &auth, subscription_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(subscription))
+}
+
+pub async fn update_webhook(
+    subscription_id: web::Path<Uuid>,
+    subscription: web::Json<NewWebhookSubscription>,
+    auth: AuthenticatedVendor,
+    locale: Locale,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let existing = load_subscription(pool.get_ref(), &auth, subscription_id.into_inner()).await?;
+    let subscription = subscription.into_inner();
+    validate_subscription(&subscription, locale).await?;
+    let updated = sqlx::query_as::<_, WebhookSubscription>(
+        r#"
+        UPDATE webhook_subscriptions
+        SET url = $1, secret = COALESCE($2, secret), events = $3,
+            active = COALESCE($4, active), updated_at = NOW()
+        WHERE id = $5
+        RETURNING *
+        "#,
+    )
+    .bind(subscription.url.trim())
+    .bind(&subscription.secret)
+    .bind(&subscription.events)
+    .bind(subscription.active)
+    .bind(existing.id)
+    .fetch_one(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+pub async fn delete_webhook(
+    subscription_id: web::Path<Uuid>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let existing = load_subscription(pool.get_ref(), &auth, subscription_id.into_inner()).await?;
+    sqlx::query("DELETE FROM webhook_subscriptions WHERE id = $1")
+        .bind(existing.id)
+        .execute(pool.get_ref())
+        .await?;
+    Ok(HttpResponse::Ok().json("Webhook subscription deleted successfully"))
+}
+
+pub async fn get_webhook_deliveries(
+    subscription_id: web::Path<Uuid>,
+    params: web::Query<ListParams>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let existing = load_subscription(pool.get_ref(), &auth, subscription_id.into_inner()).await?;
+    let limit = params.limit();
+    let offset = params.offset();
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM webhook_deliveries WHERE subscription_id = $1",
+    )
+    .bind(existing.id)
+    .fetch_one(pool.get_ref())
+    .await?;
+    let items = sqlx::query_as::<_, WebhookDelivery>(
+        r#"
+        SELECT * FROM webhook_deliveries
+        WHERE subscription_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(existing.id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(Page {
+        items,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+pub async fn get_webhook_delivery(
+    path: web::Path<(Uuid, Uuid)>,
+    auth: AuthenticatedVendor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let (subscription_id, delivery_id) = path.into_inner();
+    let existing = load_subscription(pool.get_ref(), &auth, subscription_id).await?;
+    let delivery = sqlx::query_as::<_, WebhookDelivery>(
+        "SELECT * FROM webhook_deliveries WHERE id = $1 AND subscription_id = $2",
+    )
+    .bind(delivery_id)
+    .bind(existing.id)
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Webhook delivery not found"))?;
+    let attempt_log = sqlx::query_as::<_, WebhookDeliveryAttempt>(
+        r#"
+        SELECT * FROM webhook_delivery_attempts
+        WHERE delivery_id = $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(delivery_id)
+    .fetch_all(pool.get_ref())
+    .await?;
+    Ok(HttpResponse::Ok().json(WebhookDeliveryDetail {
+        delivery,
+        attempt_log,
+    }))
+}
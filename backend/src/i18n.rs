@@ -0,0 +1,278 @@
+use crate::auth::AuthenticatedVendor;
+use crate::errors::ApiError;
+use actix_web::dev::Payload;
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::{web, FromRequest, HttpRequest};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use futures::future::LocalBoxFuture;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use unic_langid::LanguageIdentifier;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Fr,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Fr];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Fr => "fr",
+        }
+    }
+
+    // Postgres text search configuration that stems words in this language
+    pub fn text_search_config(&self) -> &'static str {
+        match self {
+            Locale::En => "english",
+            Locale::Fr => "french",
+        }
+    }
+
+    // Matches on the primary language only, so `fr-CM` and `fr-FR` both get French
+    fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        Locale::ALL.into_iter().find(|l| l.as_str() == language)
+    }
+
+    // Best supported language of an `Accept-Language` header, honouring q-values
+    pub fn negotiate(header: &str) -> Option<Self> {
+        let mut ranges: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .filter_map(|p| p.trim().strip_prefix("q="))
+                    .find_map(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        // Stable, so equally weighted languages keep the client's order
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| Locale::from_tag(tag))
+    }
+}
+
+// The vendor's saved preference wins; otherwise the browser's `Accept-Language`, then English
+impl FromRequest for Locale {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let requested = req
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Locale::negotiate)
+            .unwrap_or_default();
+        let vendor = AuthenticatedVendor::from_request(req, payload);
+        let pool = req.app_data::<web::Data<PgPool>>().cloned();
+        Box::pin(async move {
+            match (vendor.await.ok(), pool) {
+                (Some(vendor), Some(pool)) => Ok(vendor_locale(&pool, vendor.vendor_id)
+                    .await?
+                    .unwrap_or(requested)),
+                _ => Ok(requested),
+            }
+        })
+    }
+}
+
+pub async fn vendor_locale(pool: &PgPool, vendor_id: Uuid) -> Result<Option<Locale>, sqlx::Error> {
+    sqlx::query_scalar::<_, Option<Locale>>("SELECT locale FROM vendors WHERE id = $1")
+        .bind(vendor_id)
+        .fetch_optional(pool)
+        .await
+        .map(Option::flatten)
+}
+
+type Bundle = FluentBundle<FluentResource>;
+
+struct Catalog {
+    en: Bundle,
+    fr: Bundle,
+}
+
+impl Catalog {
+    fn bundle(&self, locale: Locale) -> &Bundle {
+        match locale {
+            Locale::En => &self.en,
+            Locale::Fr => &self.fr,
+        }
+    }
+}
+
+fn load_bundle(locale: Locale, source: &str) -> Bundle {
+    let language: LanguageIdentifier = locale.as_str().parse().expect("valid language id");
+    let resource = FluentResource::try_new(source.to_string())
+        .unwrap_or_else(|(_, errors)| panic!("Invalid {} catalog: {:?}", locale.as_str(), errors));
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+    // Isolation marks would end up verbatim in emails and JSON
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .unwrap_or_else(|errors| panic!("Invalid {} catalog: {:?}", locale.as_str(), errors));
+    bundle
+}
+
+static CATALOG: LazyLock<Catalog> = LazyLock::new(|| Catalog {
+    en: load_bundle(Locale::En, include_str!("../locales/en/main.ftl")),
+    fr: load_bundle(Locale::Fr, include_str!("../locales/fr/main.ftl")),
+});
+
+fn fluent_value(value: &Value) -> FluentValue<'_> {
+    match value {
+        Value::Number(n) => n
+            .as_f64()
+            .map(FluentValue::from)
+            .unwrap_or(FluentValue::None),
+        Value::String(s) => FluentValue::from(s.as_str()),
+        other => FluentValue::from(other.to_string()),
+    }
+}
+
+// `id` may name an attribute as `message.attribute`; missing entries fall back to English
+fn format(locale: Locale, id: &str, args: &BTreeMap<String, Value>) -> Option<String> {
+    let (message_id, attribute) = match id.split_once('.') {
+        Some((message_id, attribute)) => (message_id, Some(attribute)),
+        None => (id, None),
+    };
+    let bundle = CATALOG.bundle(locale);
+    let message = bundle.get_message(message_id)?;
+    let pattern = match attribute {
+        Some(attribute) => message.get_attribute(attribute)?.value(),
+        None => message.value()?,
+    };
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(name.as_str(), fluent_value(value));
+    }
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+    if !errors.is_empty() {
+        warn!("Formatting {} ({}): {:?}", id, locale.as_str(), errors);
+    }
+    Some(text.into_owned())
+}
+
+// A catalog entry with its arguments, kept unrendered so it can be shown in the reader's language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Message {
+    Catalog {
+        id: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        args: BTreeMap<String, Value>,
+    },
+    // Shown as-is, e.g. text stored before messages were localized
+    Text(String),
+}
+
+impl Message {
+    pub fn new(id: &str) -> Self {
+        Message::Catalog {
+            id: id.to_string(),
+            args: BTreeMap::new(),
+        }
+    }
+
+    pub fn arg(mut self, name: &str, value: impl Into<Value>) -> Self {
+        if let Message::Catalog { args, .. } = &mut self {
+            args.insert(name.to_string(), value.into());
+        }
+        self
+    }
+
+    pub fn render(&self, locale: Locale) -> String {
+        match self {
+            Message::Catalog { id, args } => format(locale, id, args)
+                .or_else(|| format(Locale::En, id, args))
+                .unwrap_or_else(|| {
+                    warn!("Missing message {}", id);
+                    id.clone()
+                }),
+            Message::Text(text) => text.clone(),
+        }
+    }
+}
+
+pub fn t(locale: Locale, id: &str) -> String {
+    Message::new(id).render(locale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_ids(source: &str) -> Vec<&str> {
+        source
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .filter_map(|line| line.split_once(" =").map(|(id, _)| id))
+            .collect()
+    }
+
+    #[test]
+    fn negotiate_prefers_the_highest_quality() {
+        assert_eq!(Locale::negotiate("en;q=0.5, fr-CM;q=0.9"), Some(Locale::Fr));
+        assert_eq!(Locale::negotiate("fr;q=0.4,en"), Some(Locale::En));
+    }
+
+    #[test]
+    fn negotiate_keeps_client_order_on_ties() {
+        assert_eq!(Locale::negotiate("fr-FR, en-US"), Some(Locale::Fr));
+        assert_eq!(Locale::negotiate("en_GB, fr"), Some(Locale::En));
+    }
+
+    #[test]
+    fn negotiate_skips_unsupported_and_refused_languages() {
+        assert_eq!(Locale::negotiate("de, fr;q=0.3"), Some(Locale::Fr));
+        assert_eq!(Locale::negotiate("fr;q=0, en;q=0.1"), Some(Locale::En));
+        assert_eq!(Locale::negotiate("de-DE, es"), None);
+        assert_eq!(Locale::negotiate(""), None);
+    }
+
+    #[test]
+    fn catalogs_define_the_same_messages() {
+        let en = message_ids(include_str!("../locales/en/main.ftl"));
+        let fr = message_ids(include_str!("../locales/fr/main.ftl"));
+        assert!(!en.is_empty());
+        assert_eq!(en, fr);
+    }
+
+    #[test]
+    fn render_fills_arguments_per_locale() {
+        let message = Message::new("validation-password-too-short").arg("min", 8);
+        assert_eq!(
+            message.render(Locale::En),
+            "Password must be at least 8 characters long"
+        );
+        assert_eq!(
+            message.render(Locale::Fr),
+            "Le mot de passe doit comporter au moins 8 caractères"
+        );
+    }
+
+    #[test]
+    fn render_falls_back_to_the_id() {
+        assert_eq!(t(Locale::Fr, "no-such-message"), "no-such-message");
+        assert_eq!(Message::Text("as is".into()).render(Locale::Fr), "as is");
+    }
+}
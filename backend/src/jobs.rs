@@ -0,0 +1,283 @@
+use crate::models::*;
+use crate::notifications::{self, channels::ChannelRegistry};
+use crate::verification;
+use crate::webhooks;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{PgConnection, PgPool};
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+// A job still marked running after this long is assumed to belong to a crashed worker
+const STALE_LOCK_SECONDS: f64 = 600.0;
+
+// Delays double from `base_delay_seconds` up to `max_delay_seconds`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub base_delay_seconds: u64,
+    pub max_delay_seconds: u64,
+}
+
+impl RetryPolicy {
+    // Seconds to wait after the given (1-based) failed attempt
+    pub fn delay(&self, attempt: i32) -> u64 {
+        let doublings = (attempt - 1).clamp(0, 32) as u32;
+        self.base_delay_seconds
+            .saturating_mul(2u64.saturating_pow(doublings))
+            .min(self.max_delay_seconds)
+    }
+}
+
+// Keyed by `jobs.kind` so rows whose payload no longer parses still get a policy.
+// Deliveries keep retrying for about a day so an SMTP or endpoint outage does not lose them.
+pub fn retry_policy(kind: &str) -> RetryPolicy {
+    match kind {
+        "deliver_notification" | "deliver_webhook" => RetryPolicy {
+            max_attempts: 15,
+            base_delay_seconds: 30,
+            max_delay_seconds: 4 * 3_600,
+        },
+        _ => RetryPolicy {
+            max_attempts: 5,
+            base_delay_seconds: 10,
+            max_delay_seconds: 600,
+        },
+    }
+}
+
+// Work that runs outside the request cycle; stored as `jobs.payload`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Task {
+    VerifyProduct { product_id: Uuid },
+    DeliverNotification { outbox_id: Uuid },
+    DeliverWebhook { delivery_id: Uuid },
+}
+
+impl Task {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Task::VerifyProduct { .. } => "verify_product",
+            Task::DeliverNotification { .. } => "deliver_notification",
+            Task::DeliverWebhook { .. } => "deliver_webhook",
+        }
+    }
+
+    async fn run(&self, context: &JobContext) -> anyhow::Result<()> {
+        match self {
+            Task::VerifyProduct { product_id } => {
+                verification::verify_product(&context.pool, *product_id).await?;
+            }
+            Task::DeliverNotification { outbox_id } => {
+                notifications::deliver(&context.pool, &context.channels, *outbox_id).await?;
+            }
+            Task::DeliverWebhook { delivery_id } => {
+                webhooks::deliver(&context.pool, &context.http, *delivery_id).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// What running tasks need besides their payload
+#[derive(Clone)]
+pub struct JobContext {
+    pub pool: PgPool,
+    pub channels: Arc<ChannelRegistry>,
+    pub http: reqwest::Client,
+}
+
+#[derive(Clone)]
+pub struct JobConfig {
+    workers: usize,
+    poll_interval: Duration,
+}
+
+impl JobConfig {
+    pub fn from_env() -> Self {
+        let workers = env::var("JOB_WORKERS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2);
+        let poll_interval_ms = env::var("JOB_POLL_INTERVAL_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1_000);
+        JobConfig {
+            workers,
+            poll_interval: Duration::from_millis(poll_interval_ms),
+        }
+    }
+}
+
+// Takes a connection so callers can enqueue in the same transaction as the change that needs it
+pub async fn enqueue(
+    conn: &mut PgConnection,
+    task: &Task,
+    owner_id: Option<Uuid>,
+) -> Result<Job, sqlx::Error> {
+    sqlx::query_as::<_, Job>(
+        r#"
+        INSERT INTO jobs (kind, payload, owner_id, max_attempts)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(task.kind())
+    .bind(Json(task))
+    .bind(owner_id)
+    .bind(retry_policy(task.kind()).max_attempts)
+    .fetch_one(conn)
+    .await
+}
+
+pub fn spawn_workers(context: JobContext, config: &JobConfig) {
+    for worker in 0..config.workers {
+        let context = context.clone();
+        let poll_interval = config.poll_interval;
+        tokio::spawn(async move {
+            loop {
+                match run_next(&context).await {
+                    // Keep draining while there is work
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => error!("Job worker {} could not poll the queue: {}", worker, e),
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        });
+    }
+    info!("Started {} job workers", config.workers);
+}
+
+// Claims one runnable job and runs it; returns false when the queue is empty
+async fn run_next(context: &JobContext) -> Result<bool, sqlx::Error> {
+    let pool = &context.pool;
+    // SKIP LOCKED lets concurrent workers claim different jobs without blocking each other
+    let job = sqlx::query_as::<_, Job>(
+        r#"
+        UPDATE jobs
+        SET status = 'running', attempts = attempts + 1, locked_at = NOW(), updated_at = NOW()
+        WHERE id = (
+            SELECT id FROM jobs
+            WHERE (status = 'queued' AND run_at <= NOW())
+                OR (status = 'running' AND locked_at < NOW() - make_interval(secs => $1))
+            ORDER BY run_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING *
+        "#,
+    )
+    .bind(STALE_LOCK_SECONDS)
+    .fetch_optional(pool)
+    .await?;
+    let Some(job) = job else {
+        return Ok(false);
+    };
+
+    let result = match serde_json::from_value::<Task>(job.payload.0.clone()) {
+        Ok(task) => task.run(context).await.map_err(|e| format!("{:#}", e)),
+        Err(e) => Err(format!("Unreadable job payload: {}", e)),
+    };
+    match result {
+        Ok(()) => {
+            sqlx::query(
+                "UPDATE jobs SET status = 'succeeded', locked_at = NULL, updated_at = NOW() WHERE id = $1",
+            )
+            .bind(job.id)
+            .execute(pool)
+            .await?;
+        }
+        Err(message) => fail(pool, &job, &message).await?,
+    }
+    Ok(true)
+}
+
+// Requeues with exponential backoff, or dead-letters once the attempts are used up
+async fn fail(pool: &PgPool, job: &Job, message: &str) -> Result<(), sqlx::Error> {
+    let dead = job.attempts >= job.max_attempts;
+    let delay = retry_policy(&job.kind).delay(job.attempts);
+    sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = $1, run_at = NOW() + make_interval(secs => $2), last_error = $3,
+            locked_at = NULL, updated_at = NOW()
+        WHERE id = $4
+        "#,
+    )
+    .bind(if dead {
+        JobStatus::Dead
+    } else {
+        JobStatus::Queued
+    })
+    .bind(delay as f64)
+    .bind(message)
+    .bind(job.id)
+    .execute(pool)
+    .await?;
+    if dead {
+        error!(
+            "Job {} ({}) failed for good after {} attempts: {}",
+            job.id, job.kind, job.attempts, message
+        );
+    } else {
+        warn!(
+            "Job {} ({}) failed on attempt {}, retrying in {}s: {}",
+            job.id, job.kind, job.attempts, delay, message
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Seconds between the first attempt and the job being dead-lettered
+    fn total_retry_seconds(policy: RetryPolicy) -> u64 {
+        (1..policy.max_attempts)
+            .map(|attempt| policy.delay(attempt))
+            .sum()
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        let policy = retry_policy("deliver_webhook");
+        let delays: Vec<u64> = (1..=12).map(|attempt| policy.delay(attempt)).collect();
+        assert_eq!(&delays[..4], [30, 60, 120, 240]);
+        assert_eq!(delays[11], policy.max_delay_seconds);
+        assert_eq!(policy.delay(1_000), policy.max_delay_seconds);
+        assert_eq!(policy.delay(0), policy.base_delay_seconds);
+    }
+
+    #[test]
+    fn deliveries_retry_for_hours() {
+        let tasks = [
+            Task::DeliverNotification {
+                outbox_id: Uuid::nil(),
+            },
+            Task::DeliverWebhook {
+                delivery_id: Uuid::nil(),
+            },
+        ];
+        for task in tasks {
+            let total = total_retry_seconds(retry_policy(task.kind()));
+            assert!(total >= 12 * 3_600, "{}: {}s", task.kind(), total);
+        }
+    }
+
+    #[test]
+    fn verification_gives_up_sooner() {
+        let task = Task::VerifyProduct {
+            product_id: Uuid::nil(),
+        };
+        let policy = retry_policy(task.kind());
+        assert_eq!(policy.max_attempts, 5);
+        assert!(total_retry_seconds(policy) < 3_600);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::errors::ApiError;
+use crate::models::*;
+use crate::webhooks;
+use serde_json::json;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+// Moves a product to `to` if the state machine allows it and records the transition.
+// Locks the product row, so run it inside the caller's transaction.
+pub async fn transition(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    to: ProductStatus,
+    actor_id: Option<Uuid>,
+    reason: Option<&str>,
+) -> Result<StatusTransition, ApiError> {
+    let from = sqlx::query_scalar::<_, ProductStatus>(
+        "SELECT status FROM products WHERE id = $1 FOR UPDATE",
+    )
+    .bind(product_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| ApiError::not_found("Product not found"))?;
+    if !from.can_transition_to(to) {
+        return Err(ApiError::Conflict {
+            message: format!(
+                "Cannot move product from {} to {}",
+                from.as_str(),
+                to.as_str()
+            ),
+            details: Some(json!({ "from": from, "to": to })),
+        });
+    }
+    let product = sqlx::query_as::<_, Product>(
+        "UPDATE products SET status = $1, updated_at = NOW() WHERE id = $2 RETURNING *",
+    )
+    .bind(to)
+    .bind(product_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let transition = sqlx::query_as::<_, StatusTransition>(
+        r#"
+        INSERT INTO product_status_history (product_id, from_status, to_status, actor_id, reason)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(product_id)
+    .bind(from)
+    .bind(to)
+    .bind(actor_id)
+    .bind(reason)
+    .fetch_one(&mut *conn)
+    .await?;
+    webhooks::emit(
+        conn,
+        WebhookEvent::for_transition(to),
+        product.vendor_id,
+        &json!({ "product": product, "transition": transition }),
+    )
+    .await?;
+    Ok(transition)
+}
+
+pub async fn history(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+) -> Result<Vec<StatusTransition>, sqlx::Error> {
+    sqlx::query_as::<_, StatusTransition>(
+        r#"
+        SELECT * FROM product_status_history
+        WHERE product_id = $1
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(conn)
+    .await
+}
@@ -1,13 +1,24 @@
+mod auth;
 mod db;
+mod errors;
 mod handlers;
+mod i18n;
+mod jobs;
+mod lifecycle;
 mod models;
+mod notifications;
+mod validation;
+mod verification;
+mod webhooks;
 
 use actix_cors::Cors;
 use actix_files::Files;
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use dotenv::dotenv;
+use errors::ApiError;
 use handlers::*;
 use std::env;
+use std::sync::Arc;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -15,9 +26,37 @@ async fn main() -> std::io::Result<()> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
     let pool = db::create_pool().await.expect("Failed to create pool");
-    db::init_db(&pool)
+
+    // `product-listing-backend migrate <run|revert|status>` manages the schema without serving
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return db::migrate_command(&pool, &args[1..])
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()));
+    }
+
+    db::run_migrations(&pool)
         .await
-        .expect("Failed to initialize database");
+        .expect("Failed to run database migrations");
+
+    let auth_config = auth::AuthConfig::from_env();
+    // `product-listing-backend password-setup <email>` lets a vendor without a password choose one
+    if args.first().map(String::as_str) == Some("password-setup") {
+        return auth::password_setup_command(&pool, &auth_config, &args[1..])
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()));
+    }
+    let channels = notifications::channels::ChannelRegistry::from_env()
+        .expect("Failed to configure notification channels");
+    jobs::spawn_workers(
+        jobs::JobContext {
+            pool: pool.clone(),
+            channels: Arc::new(channels),
+            http: webhooks::http_client().expect("Failed to build HTTP client"),
+        },
+        &jobs::JobConfig::from_env(),
+    );
+    let notification_hub = notifications::spawn_listener(pool.clone());
 
     let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
     let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
@@ -34,21 +73,158 @@ async fn main() -> std::io::Result<()> {
             .wrap(Logger::default())
             .wrap(cors)
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(auth_config.clone()))
+            .app_data(web::Data::new(notification_hub.clone()))
+            // Malformed bodies, query strings and paths get the same error envelope as handlers
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|err, _| ApiError::bad_request(err.to_string()).into()),
+            )
+            .app_data(
+                web::QueryConfig::default()
+                    .error_handler(|err, _| ApiError::bad_request(err.to_string()).into()),
+            )
+            .app_data(
+                web::PathConfig::default()
+                    .error_handler(|err, _| ApiError::bad_request(err.to_string()).into()),
+            )
             .route("/health", web::get().to(health_check))
             .service(
                 web::scope("/api")
+                    .route("/auth/login", web::post().to(login))
+                    .route("/auth/me", web::get().to(current_vendor))
+                    .route(
+                        "/auth/password-setup",
+                        web::post().to(complete_password_setup),
+                    )
                     .route("/categories", web::get().to(get_categories))
+                    .route("/categories", web::post().to(create_category))
+                    .route("/categories/tree", web::get().to(get_category_tree))
+                    .route("/categories/{key}", web::get().to(get_category))
+                    .route(
+                        "/categories/{id}/breadcrumb",
+                        web::get().to(get_category_breadcrumb),
+                    )
+                    .route("/categories/{id}", web::put().to(update_category))
+                    .route("/categories/{id}", web::delete().to(delete_category))
+                    .route("/categories/{id}/merge", web::post().to(merge_category))
                     .route("/tags", web::get().to(get_tags))
+                    .route("/tags", web::post().to(create_tag))
+                    .route("/tags/popular", web::get().to(get_popular_tags))
+                    .route("/tags/proposals", web::get().to(get_tag_proposals))
+                    .route("/tags/proposals", web::post().to(propose_tag))
+                    .route("/tags/{id}", web::put().to(rename_tag))
+                    .route("/tags/{id}", web::delete().to(delete_tag))
+                    .route("/tags/{id}/merge", web::post().to(merge_tag))
                     .route("/products", web::get().to(get_products))
                     .route("/products", web::post().to(create_product))
+                    .route("/products/search", web::get().to(search_products))
+                    .route("/products/{id}", web::get().to(get_product))
                     .route("/products/{id}", web::delete().to(delete_product))
                     .route("/products/{id}", web::put().to(update_product))
                     .route("/upload", web::post().to(upload_file))
                     .route("/products/{id}/draft", web::post().to(save_draft))
                     .route("/products/{id}/submit", web::post().to(submit_product))
+                    .route("/products/{id}/archive", web::post().to(archive_product))
+                    .route("/products/{id}/restore", web::post().to(restore_product))
+                    .route("/products/{id}/history", web::get().to(get_product_history))
+                    .route(
+                        "/products/{id}/verification",
+                        web::get().to(get_verification_report),
+                    )
+                    .route("/moderation/queue", web::get().to(get_moderation_queue))
+                    .route("/moderation/tags", web::get().to(get_tag_queue))
+                    .route("/moderation/tags/{id}/approve", web::post().to(approve_tag))
+                    .route("/moderation/tags/{id}/reject", web::post().to(reject_tag))
+                    .route(
+                        "/moderation/products/{id}/approve",
+                        web::post().to(approve_product),
+                    )
+                    .route(
+                        "/moderation/products/{id}/reject",
+                        web::post().to(reject_product),
+                    )
+                    .route(
+                        "/moderation/products/{id}/suspend",
+                        web::post().to(suspend_product),
+                    )
+                    .route(
+                        "/moderation/products/{id}/reinstate",
+                        web::post().to(reinstate_product),
+                    )
+                    .route("/jobs", web::get().to(get_jobs))
+                    .route("/jobs/{id}", web::get().to(get_job))
+                    .route("/jobs/{id}/retry", web::post().to(retry_job))
+                    .route("/webhooks", web::get().to(get_webhooks))
+                    .route("/webhooks", web::post().to(create_webhook))
+                    .route("/webhooks/{id}", web::get().to(get_webhook))
+                    .route("/webhooks/{id}", web::put().to(update_webhook))
+                    .route("/webhooks/{id}", web::delete().to(delete_webhook))
+                    .route(
+                        "/webhooks/{id}/deliveries",
+                        web::get().to(get_webhook_deliveries),
+                    )
+                    .route(
+                        "/webhooks/{id}/deliveries/{delivery_id}",
+                        web::get().to(get_webhook_delivery),
+                    )
+                    .route("/vendors", web::get().to(get_vendors))
+                    .route("/vendors", web::post().to(create_vendor))
+                    .route("/vendors/{id}", web::get().to(get_vendor))
+                    .route("/vendors/{id}", web::put().to(update_vendor))
+                    .route("/vendors/{id}", web::delete().to(delete_vendor))
+                    .route("/vendors/{id}/products", web::get().to(get_vendor_products))
                     .route(
                         "/vendors/{id}/notifications",
                         web::get().to(get_notifications),
+                    )
+                    .route(
+                        "/vendors/{id}/notification-preferences",
+                        web::get().to(get_channel_preferences),
+                    )
+                    .route(
+                        "/vendors/{id}/notification-preferences",
+                        web::put().to(update_channel_preferences),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/stream",
+                        web::get().to(stream_notifications),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/stream-token",
+                        web::post().to(create_stream_token),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/kinds",
+                        web::get().to(get_notification_kinds),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/unread-count",
+                        web::get().to(get_unread_count),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/read",
+                        web::post().to(mark_notifications_read),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/read-all",
+                        web::post().to(mark_all_notifications_read),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/{notification_id}",
+                        web::delete().to(delete_notification),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/{notification_id}/read",
+                        web::post().to(mark_notification_read),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/{notification_id}/unread",
+                        web::post().to(mark_notification_unread),
+                    )
+                    .route(
+                        "/vendors/{id}/notifications/{notification_id}/archive",
+                        web::post().to(archive_notification),
                     ),
             )
             .service(Files::new("/uploads", "uploads"))
@@ -1,5 +1,10 @@
+use crate::auth::Role;
+use crate::i18n::{Locale, Message};
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::FromRow;
 use uuid::Uuid;
 
@@ -9,58 +14,640 @@ pub struct Product {
     pub vendor_id: Uuid,
     pub name: String,
     pub description: String,
-    pub price: f64,
+    // Serialized as a string so JavaScript clients never see a float
+    pub price: Decimal,
+    pub currency: Currency,
     pub image_url: String,
-    pub is_draft: bool,
-    pub is_verified: bool,
+    pub status: ProductStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NewProduct {
-    pub vendor_id: Uuid,
     pub name: String,
     pub description: String,
-    pub price: f64,
+    // Accepts a string or a number; strings avoid float rounding on the way in
+    pub price: Decimal,
+    #[serde(default)]
+    pub currency: Currency,
     pub image_url: String,
-    pub is_draft: bool,
+    // The primary category; older clients send only this
     pub category_id: Option<Uuid>,
+    // Every category the product belongs to. The primary is `category_id` when given, otherwise the first entry.
+    #[serde(default)]
+    pub category_ids: Option<Vec<Uuid>>,
     pub tag_ids: Option<Vec<Uuid>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+impl NewProduct {
+    // All categories with the primary one first
+    pub fn all_category_ids(&self) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = self.category_id.into_iter().collect();
+        for id in self.category_ids.iter().flatten() {
+            if !ids.contains(id) {
+                ids.push(*id);
+            }
+        }
+        ids
+    }
+}
+
+// ISO-4217 codes the marketplace accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "UPPERCASE")]
+#[sqlx(type_name = "varchar", rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Xaf,
+    Xof,
+    Ngn,
+    Eur,
+    Usd,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 5] = [
+        Currency::Xaf,
+        Currency::Xof,
+        Currency::Ngn,
+        Currency::Eur,
+        Currency::Usd,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Currency::Xaf => "XAF",
+            Currency::Xof => "XOF",
+            Currency::Ngn => "NGN",
+            Currency::Eur => "EUR",
+            Currency::Usd => "USD",
+        }
+    }
+
+    // Decimal places a price may have; the CFA francs have no subunit in use
+    pub fn minor_units(self) -> u32 {
+        match self {
+            Currency::Xaf | Currency::Xof => 0,
+            Currency::Ngn | Currency::Eur | Currency::Usd => 2,
+        }
+    }
+
+    // Lower edges of the price facet's buckets; the last bucket is open-ended
+    pub fn price_bucket_edges(self) -> [Decimal; 6] {
+        match self {
+            Currency::Xaf | Currency::Xof => [
+                dec!(0),
+                dec!(1000),
+                dec!(5000),
+                dec!(10000),
+                dec!(50000),
+                dec!(100000),
+            ],
+            Currency::Ngn => [
+                dec!(0),
+                dec!(2500),
+                dec!(10000),
+                dec!(25000),
+                dec!(100000),
+                dec!(250000),
+            ],
+            Currency::Eur | Currency::Usd => {
+                [dec!(0), dec!(5), dec!(10), dec!(20), dec!(100), dec!(200)]
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "product_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ProductStatus {
+    Draft,
+    Submitted,
+    InReview,
+    Live,
+    Rejected,
+    Archived,
+    Suspended,
+}
+
+impl ProductStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProductStatus::Draft => "draft",
+            ProductStatus::Submitted => "submitted",
+            ProductStatus::InReview => "in_review",
+            ProductStatus::Live => "live",
+            ProductStatus::Rejected => "rejected",
+            ProductStatus::Archived => "archived",
+            ProductStatus::Suspended => "suspended",
+        }
+    }
+
+    pub fn can_transition_to(self, next: ProductStatus) -> bool {
+        use ProductStatus::*;
+        matches!(
+            (self, next),
+            (Draft, Submitted)
+                | (Draft, Archived)
+                | (Submitted, InReview)
+                | (Submitted, Rejected)
+                | (Submitted, Draft)
+                | (InReview, Live)
+                | (InReview, Rejected)
+                | (InReview, Draft)
+                | (Rejected, Draft)
+                | (Rejected, Submitted)
+                | (Rejected, Archived)
+                | (Live, Archived)
+                | (Live, Suspended)
+                | (Archived, Draft)
+                | (Suspended, Live)
+                | (Suspended, Archived)
+        )
+    }
+
+    // Content can only change while nobody is reviewing it and it is not public
+    pub fn is_editable(self) -> bool {
+        matches!(self, ProductStatus::Draft | ProductStatus::Rejected)
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct StatusTransition {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub from_status: ProductStatus,
+    pub to_status: ProductStatus,
+    pub actor_id: Option<Uuid>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransitionRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "job_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    // Out of attempts; stays put until a moderator retries it
+    Dead,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: Json<serde_json::Value>,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub owner_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobFilters {
+    pub status: Option<JobStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Category {
     pub id: Uuid,
     pub name: String,
+    // Unique across the whole tree, so it identifies a category on its own in URLs
+    pub slug: String,
+    pub parent_id: Option<Uuid>,
+    // Order among siblings, lowest first
+    pub position: i32,
     pub created_at: DateTime<Utc>,
 }
 
+// A null `parent_id` makes a top-level category. On update `parent_id`, `slug` and `position`
+// keep their current values when omitted; on create they are derived from the name and appended last.
+#[derive(Debug, Deserialize)]
+pub struct NewCategory {
+    pub name: String,
+    // Outer `None` when the field is absent, `Some(None)` for an explicit null
+    #[serde(default, deserialize_with = "present")]
+    pub parent_id: Option<Option<Uuid>>,
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub position: Option<i32>,
+}
+
+impl NewCategory {
+    pub fn parent(&self) -> Option<Uuid> {
+        self.parent_id.flatten()
+    }
+}
+
+// Only called when the field is present, so a null still counts as given
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub children: Vec<CategoryNode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "tag_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TagStatus {
+    // Proposed by a vendor; cannot be attached to products until approved
+    Pending,
+    Approved,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Tag {
     pub id: Uuid,
     pub name: String,
+    pub status: TagStatus,
+    pub proposed_by: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ProductCategory {
-    pub product_id: Uuid,
-    pub category_id: Uuid,
+#[derive(Debug, Deserialize)]
+pub struct NewTag {
+    pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ProductTag {
-    pub product_id: Uuid,
-    pub tag_id: Uuid,
+#[derive(Debug, Default, Deserialize)]
+pub struct CountParams {
+    #[serde(default)]
+    pub with_counts: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Products by status; `total` covers every status, including those not broken out here
+#[derive(Debug, Serialize, FromRow)]
+pub struct ProductCounts {
+    pub live: i64,
+    pub draft: i64,
+    pub rejected: i64,
+    pub total: i64,
+}
+
+// Counts products anywhere in the category's subtree, once each, as listed with `include_descendants`
+#[derive(Debug, Serialize, FromRow)]
+pub struct CategoryWithCounts {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub category: Category,
+    #[sqlx(flatten)]
+    pub product_counts: ProductCounts,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct TagWithCounts {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub tag: Tag,
+    #[sqlx(flatten)]
+    pub product_counts: ProductCounts,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PopularTagsParams {
+    pub days: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+pub const DEFAULT_POPULAR_DAYS: i64 = 30;
+pub const MAX_POPULAR_DAYS: i64 = 365;
+pub const DEFAULT_POPULAR_TAGS: i64 = 10;
+
+impl PopularTagsParams {
+    pub fn days(&self) -> i64 {
+        self.days
+            .unwrap_or(DEFAULT_POPULAR_DAYS)
+            .clamp(1, MAX_POPULAR_DAYS)
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_POPULAR_TAGS)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct PopularTag {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub tag: Tag,
+    pub product_count: i64,
+}
+
+// Moves everything attached to the source onto `into`, then deletes the source
+#[derive(Debug, Deserialize)]
+pub struct MergeRequest {
+    pub into: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Vendor {
     pub id: Uuid,
     pub name: String,
     pub email: String,
+    pub role: Role,
+    // None follows the browser's Accept-Language
+    pub locale: Option<Locale>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewVendor {
+    pub name: String,
+    pub email: String,
+    // Required on registration, optional when updating a vendor
+    #[serde(default)]
+    pub password: Option<String>,
+    // Left unchanged when omitted on update
+    #[serde(default)]
+    pub locale: Option<Locale>,
+    // Required on update when changing the email or password
+    #[serde(default)]
+    pub current_password: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+// `token` comes from the `password-setup` admin command
+#[derive(Debug, Deserialize)]
+pub struct PasswordSetupRequest {
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub vendor: Vendor,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct VendorSummary {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductDetail {
+    #[serde(flatten)]
+    pub product: Product,
+    // The primary category, kept for clients that only know about one
+    pub category: Option<Category>,
+    // Primary first, then by name
+    pub categories: Vec<Category>,
+    pub tags: Vec<Tag>,
+    pub vendor: VendorSummary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleResult {
+    pub rule: String,
+    pub passed: bool,
+    pub reason: String,
+    // What `reason` was rendered from; absent on reports stored before localization
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Message>,
+}
+
+impl RuleResult {
+    pub fn localize(&mut self, locale: Locale) {
+        if let Some(message) = &self.message {
+            self.reason = message.render(locale);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct VerificationReport {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub passed: bool,
+    pub rules: Json<Vec<RuleResult>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "varchar")]
+pub enum WebhookEvent {
+    #[serde(rename = "product.created")]
+    #[sqlx(rename = "product.created")]
+    Created,
+    #[serde(rename = "product.updated")]
+    #[sqlx(rename = "product.updated")]
+    Updated,
+    #[serde(rename = "product.submitted")]
+    #[sqlx(rename = "product.submitted")]
+    Submitted,
+    // Passed automatic verification and is waiting for a moderator
+    #[serde(rename = "product.verified")]
+    #[sqlx(rename = "product.verified")]
+    Verified,
+    #[serde(rename = "product.published")]
+    #[sqlx(rename = "product.published")]
+    Published,
+    #[serde(rename = "product.rejected")]
+    #[sqlx(rename = "product.rejected")]
+    Rejected,
+    // Any other transition (back to draft, archived, suspended)
+    #[serde(rename = "product.status_changed")]
+    #[sqlx(rename = "product.status_changed")]
+    StatusChanged,
+    #[serde(rename = "product.deleted")]
+    #[sqlx(rename = "product.deleted")]
+    Deleted,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::Created => "product.created",
+            WebhookEvent::Updated => "product.updated",
+            WebhookEvent::Submitted => "product.submitted",
+            WebhookEvent::Verified => "product.verified",
+            WebhookEvent::Published => "product.published",
+            WebhookEvent::Rejected => "product.rejected",
+            WebhookEvent::StatusChanged => "product.status_changed",
+            WebhookEvent::Deleted => "product.deleted",
+        }
+    }
+
+    pub fn for_transition(to: ProductStatus) -> Self {
+        match to {
+            ProductStatus::Submitted => WebhookEvent::Submitted,
+            ProductStatus::InReview => WebhookEvent::Verified,
+            ProductStatus::Live => WebhookEvent::Published,
+            ProductStatus::Rejected => WebhookEvent::Rejected,
+            ProductStatus::Draft | ProductStatus::Archived | ProductStatus::Suspended => {
+                WebhookEvent::StatusChanged
+            }
+        }
+    }
+}
+
+// Subscriptions store their filter as a VARCHAR[] column
+impl sqlx::postgres::PgHasArrayType for WebhookEvent {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_varchar")
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct WebhookSubscription {
+    pub id: Uuid,
+    // None for global subscriptions
+    pub vendor_id: Option<Uuid>,
+    pub url: String,
+    // The secret is deliberately not loaded; it is only returned once, on creation
+    pub events: Vec<WebhookEvent>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewWebhookSubscription {
+    pub url: String,
+    // Generated when omitted; setting it on update rotates the secret
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    pub active: Option<bool>,
+    // Receive events for every vendor; moderators only, and fixed once created
+    #[serde(default)]
+    pub global: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhookSubscription {
+    #[serde(flatten)]
+    pub subscription: WebhookSubscription,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event: WebhookEvent,
+    pub payload: Json<serde_json::Value>,
+    pub attempts: i32,
+    pub last_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct WebhookDeliveryAttempt {
+    pub id: Uuid,
+    pub delivery_id: Uuid,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryDetail {
+    #[serde(flatten)]
+    pub delivery: WebhookDelivery,
+    pub attempt_log: Vec<WebhookDeliveryAttempt>,
+}
+
+// What a notification is about; selects the templates its message and emails are rendered from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "notification_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    // Free text only; rows written before notifications were typed
+    General,
+    VerificationPassed,
+    VerificationFailed,
+    ModerationApproved,
+    ModerationRejected,
+    ProductSuspended,
+    ProductReinstated,
+}
+
+// Template variables for a notification, stored as `notifications.payload`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_name: Option<String>,
+    // Failed verification rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<Message>,
+    // Moderator's explanation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct NotificationKindCount {
+    pub kind: NotificationKind,
+    pub total: i64,
+    pub unread: i64,
+}
+
+// Outbound delivery channels; the in-app inbox is always on and not listed here
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    Email,
+}
+
+impl ChannelKind {
+    pub const ALL: [ChannelKind; 1] = [ChannelKind::Email];
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ChannelPreference {
+    pub channel: ChannelKind,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct OutboxMessage {
+    pub id: Uuid,
+    pub notification_id: Uuid,
+    pub channel: ChannelKind,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -68,7 +655,331 @@ pub struct Notification {
     pub id: Uuid,
     pub vendor_id: Uuid,
     pub product_id: Uuid,
+    pub kind: NotificationKind,
+    pub payload: Json<NotificationPayload>,
+    // Rendered from `kind` and `payload` when read; the stored text is kept for older clients
     pub message: String,
     pub is_read: bool,
     pub created_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
+}
+
+// Browsers' EventSource cannot set headers, so the stream also takes these from the query string
+#[derive(Debug, Default, Deserialize)]
+pub struct StreamParams {
+    pub token: Option<String>,
+    pub last_event_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamTokenResponse {
+    pub token: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationFilters {
+    pub is_read: Option<bool>,
+    pub kind: Option<NotificationKind>,
+    // The inbox hides archived notifications unless asked for them
+    #[serde(default)]
+    pub archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationIds {
+    pub ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnreadCount {
+    pub unread: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdatedCount {
+    pub updated: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSortField {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    Price,
+    Name,
+}
+
+impl ProductSortField {
+    pub fn column(self) -> &'static str {
+        match self {
+            ProductSortField::CreatedAt => "p.created_at",
+            ProductSortField::UpdatedAt => "p.updated_at",
+            ProductSortField::Price => "p.price",
+            ProductSortField::Name => "p.name",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortDirection {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+}
+
+// Query string filters shared by every product listing endpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct ProductFilters {
+    pub vendor_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    // Also match products in any subcategory of `category_id`
+    #[serde(default)]
+    pub include_descendants: bool,
+    // Comma-separated list of tag ids
+    pub tag_ids: Option<String>,
+    pub tag_match: Option<TagMatch>,
+    pub status: Option<ProductStatus>,
+    pub currency: Option<Currency>,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+}
+
+impl ProductFilters {
+    // Sorted and deduplicated, since `tag_match=all` compares against the number of ids
+    pub fn parsed_tag_ids(&self) -> Result<Vec<Uuid>, uuid::Error> {
+        let mut ids = match &self.tag_ids {
+            Some(raw) => raw
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(Uuid::parse_str)
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+        ids.sort_unstable();
+        ids.dedup();
+        Ok(ids)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListParams {
+    pub sort_by: Option<ProductSortField>,
+    pub order: Option<SortDirection>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+impl ListParams {
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    pub q: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ProductSearchHit {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub product: Product,
+    pub rank: f32,
+    // Description excerpt with matched terms wrapped in <mark> tags
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FacetParams {
+    #[serde(default)]
+    pub facets: bool,
+}
+
+pub const MAX_FACET_VALUES: i64 = 50;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct FacetValue {
+    pub id: Uuid,
+    pub name: String,
+    pub count: i64,
+}
+
+// `min` inclusive, `max` exclusive; `max` is null for the top bucket
+#[derive(Debug, Serialize)]
+pub struct PriceBucket {
+    pub min: Decimal,
+    pub max: Option<Decimal>,
+    pub count: i64,
+}
+
+// Buckets for one currency, since amounts in different currencies are not comparable
+#[derive(Debug, Serialize)]
+pub struct PriceFacet {
+    pub currency: Currency,
+    pub buckets: Vec<PriceBucket>,
+}
+
+// Counts over every product matching the current filters, not just the returned page
+#[derive(Debug, Serialize)]
+pub struct Facets {
+    pub categories: Vec<FacetValue>,
+    pub tags: Vec<FacetValue>,
+    pub vendors: Vec<FacetValue>,
+    // One entry per currency with at least one matching product
+    pub price: Vec<PriceFacet>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FacetedPage<T> {
+    #[serde(flatten)]
+    pub page: Page<T>,
+    pub facets: Facets,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const STATUSES: [ProductStatus; 7] = [
+        ProductStatus::Draft,
+        ProductStatus::Submitted,
+        ProductStatus::InReview,
+        ProductStatus::Live,
+        ProductStatus::Rejected,
+        ProductStatus::Archived,
+        ProductStatus::Suspended,
+    ];
+
+    #[test]
+    fn transition_table() {
+        use ProductStatus::*;
+        let allowed = |from: ProductStatus| -> &[ProductStatus] {
+            match from {
+                Draft => &[Submitted, Archived],
+                Submitted => &[InReview, Rejected, Draft],
+                InReview => &[Live, Rejected, Draft],
+                Live => &[Archived, Suspended],
+                Rejected => &[Draft, Submitted, Archived],
+                Archived => &[Draft],
+                Suspended => &[Live, Archived],
+            }
+        };
+        for from in STATUSES {
+            for to in STATUSES {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed(from).contains(&to),
+                    "{} -> {}",
+                    from.as_str(),
+                    to.as_str()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn only_draft_and_rejected_are_editable() {
+        for status in STATUSES {
+            assert_eq!(
+                status.is_editable(),
+                matches!(status, ProductStatus::Draft | ProductStatus::Rejected),
+                "{}",
+                status.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn parsed_tag_ids_are_deduplicated() {
+        let id = Uuid::new_v4();
+        let filters = ProductFilters {
+            tag_ids: Some(format!("{id}, {id},,{id}")),
+            ..Default::default()
+        };
+        assert_eq!(filters.parsed_tag_ids().unwrap(), [id]);
+        let filters = ProductFilters {
+            tag_ids: Some("not-a-uuid".to_string()),
+            ..Default::default()
+        };
+        assert!(filters.parsed_tag_ids().is_err());
+    }
+
+    #[test]
+    fn category_parent_id_distinguishes_absent_from_null() {
+        let parent = Uuid::new_v4();
+        let parse =
+            |value: serde_json::Value| -> NewCategory { serde_json::from_value(value).unwrap() };
+        assert_eq!(parse(json!({ "name": "Bags" })).parent_id, None);
+        assert_eq!(
+            parse(json!({ "name": "Bags", "parent_id": null })).parent_id,
+            Some(None)
+        );
+        assert_eq!(
+            parse(json!({ "name": "Bags", "parent_id": parent })).parent_id,
+            Some(Some(parent))
+        );
+    }
+
+    #[test]
+    fn currency_codes_round_trip() {
+        for currency in Currency::ALL {
+            let json = serde_json::to_value(currency).unwrap();
+            assert_eq!(json, json!(currency.as_str()));
+            assert_eq!(serde_json::from_value::<Currency>(json).unwrap(), currency);
+        }
+        assert!(serde_json::from_value::<Currency>(json!("xaf")).is_err());
+    }
+
+    #[test]
+    fn price_bucket_edges_start_at_zero_and_increase() {
+        for currency in Currency::ALL {
+            let edges = currency.price_bucket_edges();
+            assert_eq!(edges[0], Decimal::ZERO);
+            assert!(edges.windows(2).all(|w| w[0] < w[1]), "{:?}", currency);
+        }
+    }
 }
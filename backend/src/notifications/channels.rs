@@ -0,0 +1,142 @@
+use crate::models::*;
+use anyhow::Context;
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::stub::AsyncStubTransport;
+use lettre::{AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::info;
+use std::collections::HashMap;
+use std::env;
+
+// A way of reaching a vendor outside the app; implementations send one rendered outbox row
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    fn kind(&self) -> ChannelKind;
+    async fn send(&self, message: &OutboxMessage) -> anyhow::Result<()>;
+}
+
+pub enum Mailer {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    // Writes one .eml file per message; the default for local development
+    File(AsyncFileTransport<Tokio1Executor>),
+    // Accepts and drops every message
+    Memory(AsyncStubTransport),
+}
+
+impl Mailer {
+    // MAILER=smtp|file|memory, see .env.example for the settings each one reads
+    pub fn from_env() -> anyhow::Result<Self> {
+        match env::var("MAILER").as_deref().unwrap_or("file") {
+            "smtp" => {
+                let host =
+                    env::var("SMTP_HOST").context("SMTP_HOST must be set when MAILER=smtp")?;
+                let mut builder = match env::var("SMTP_TLS").as_deref().unwrap_or("starttls") {
+                    "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?,
+                    "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)?,
+                    "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+                    other => anyhow::bail!("Unknown SMTP_TLS mode: {}", other),
+                };
+                if let Ok(port) = env::var("SMTP_PORT") {
+                    builder = builder.port(port.parse().context("SMTP_PORT must be a number")?);
+                }
+                if let (Ok(username), Ok(password)) =
+                    (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD"))
+                {
+                    builder = builder.credentials(Credentials::new(username, password));
+                }
+                Ok(Mailer::Smtp(builder.build()))
+            }
+            "file" => {
+                let dir = env::var("MAIL_DIR").unwrap_or_else(|_| "mail".to_string());
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create mail directory {}", dir))?;
+                Ok(Mailer::File(AsyncFileTransport::new(dir)))
+            }
+            "memory" => Ok(Mailer::Memory(AsyncStubTransport::new_ok())),
+            other => anyhow::bail!("Unknown MAILER: {}", other),
+        }
+    }
+
+    async fn send(&self, email: Message) -> anyhow::Result<()> {
+        match self {
+            Mailer::Smtp(transport) => {
+                transport.send(email).await?;
+            }
+            Mailer::File(transport) => {
+                transport.send(email).await?;
+            }
+            Mailer::Memory(transport) => {
+                transport.send(email).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct EmailChannel {
+    mailer: Mailer,
+    from: Mailbox,
+}
+
+impl EmailChannel {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let from = env::var("MAIL_FROM")
+            .unwrap_or_else(|_| "Made in Cameroon <no-reply@localhost>".to_string())
+            .parse()
+            .context("MAIL_FROM is not a valid mailbox")?;
+        Ok(EmailChannel {
+            mailer: Mailer::from_env()?,
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Email
+    }
+
+    async fn send(&self, message: &OutboxMessage) -> anyhow::Result<()> {
+        let to: Mailbox = message
+            .recipient
+            .parse()
+            .with_context(|| format!("Invalid recipient {}", message.recipient))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&message.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.clone())?;
+        self.mailer.send(email).await?;
+        info!(
+            "Emailed {} about notification {}",
+            message.recipient, message.notification_id
+        );
+        Ok(())
+    }
+}
+
+pub struct ChannelRegistry {
+    channels: HashMap<ChannelKind, Box<dyn NotificationChannel>>,
+}
+
+impl ChannelRegistry {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut registry = ChannelRegistry {
+            channels: HashMap::new(),
+        };
+        registry.register(Box::new(EmailChannel::from_env()?));
+        Ok(registry)
+    }
+
+    fn register(&mut self, channel: Box<dyn NotificationChannel>) {
+        self.channels.insert(channel.kind(), channel);
+    }
+
+    pub fn get(&self, kind: ChannelKind) -> Option<&dyn NotificationChannel> {
+        self.channels.get(&kind).map(|channel| channel.as_ref())
+    }
+}
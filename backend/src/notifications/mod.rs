@@ -0,0 +1,207 @@
+pub mod channels;
+pub mod templates;
+
+use crate::i18n::Locale;
+use crate::jobs::{self, Task};
+use crate::models::*;
+use channels::ChannelRegistry;
+use log::{error, info, warn};
+use serde::Deserialize;
+use sqlx::postgres::PgListener;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgConnection, PgPool};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+#[derive(Debug, FromRow)]
+struct Recipient {
+    vendor_id: Uuid,
+    vendor_name: String,
+    vendor_email: String,
+    product_name: String,
+    vendor_locale: Option<Locale>,
+}
+
+// Notifies the vendor that owns `product_id` in the app and queues a message on every
+// channel they have not opted out of. Runs on the caller's connection so it can join a transaction.
+pub async fn notify_product_vendor(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    kind: NotificationKind,
+    mut payload: NotificationPayload,
+) -> Result<(), sqlx::Error> {
+    let recipient = sqlx::query_as::<_, Recipient>(
+        r#"
+        SELECT v.id AS vendor_id, v.name AS vendor_name, v.email AS vendor_email,
+            p.name AS product_name, v.locale AS vendor_locale
+        FROM products p
+        JOIN vendors v ON v.id = p.vendor_id
+        WHERE p.id = $1
+        "#,
+    )
+    .bind(product_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(recipient) = recipient else {
+        return Ok(());
+    };
+    payload
+        .product_name
+        .get_or_insert_with(|| recipient.product_name.clone());
+    // Stored and emailed in the vendor's language; the inbox re-renders for whoever reads it
+    let locale = recipient.vendor_locale.unwrap_or_default();
+    let message = templates::render_message(kind, &payload, "", locale);
+    let notification_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO notifications (vendor_id, product_id, kind, payload, message)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(recipient.vendor_id)
+    .bind(product_id)
+    .bind(kind)
+    .bind(Json(&payload))
+    .bind(&message)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let disabled = sqlx::query_scalar::<_, ChannelKind>(
+        "SELECT channel FROM vendor_channel_preferences WHERE vendor_id = $1 AND NOT enabled",
+    )
+    .bind(recipient.vendor_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let rendered =
+        templates::render_email(kind, &recipient.vendor_name, &payload, &message, locale);
+    for channel in ChannelKind::ALL {
+        if disabled.contains(&channel) {
+            continue;
+        }
+        let address = match channel {
+            ChannelKind::Email => &recipient.vendor_email,
+        };
+        let outbox_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO notification_outbox (notification_id, channel, recipient, subject, body)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            "#,
+        )
+        .bind(notification_id)
+        .bind(channel)
+        .bind(address)
+        .bind(&rendered.subject)
+        .bind(&rendered.body)
+        .fetch_one(&mut *conn)
+        .await?;
+        jobs::enqueue(
+            &mut *conn,
+            &Task::DeliverNotification { outbox_id },
+            Some(recipient.vendor_id),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Sends one outbox row; failures are recorded on the row and left to the job runner to retry
+pub async fn deliver(
+    pool: &PgPool,
+    channels: &ChannelRegistry,
+    outbox_id: Uuid,
+) -> anyhow::Result<()> {
+    let message = sqlx::query_as::<_, OutboxMessage>(
+        "SELECT * FROM notification_outbox WHERE id = $1 AND sent_at IS NULL",
+    )
+    .bind(outbox_id)
+    .fetch_optional(pool)
+    .await?;
+    // Already sent, or the notification was deleted in the meantime
+    let Some(message) = message else {
+        return Ok(());
+    };
+    let channel = channels
+        .get(message.channel)
+        .ok_or_else(|| anyhow::anyhow!("No {:?} channel is configured", message.channel))?;
+    match channel.send(&message).await {
+        Ok(()) => {
+            sqlx::query(
+                "UPDATE notification_outbox SET sent_at = NOW(), attempts = attempts + 1 WHERE id = $1",
+            )
+            .bind(outbox_id)
+            .execute(pool)
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            sqlx::query(
+                "UPDATE notification_outbox SET attempts = attempts + 1, last_error = $1 WHERE id = $2",
+            )
+            .bind(e.to_string())
+            .bind(outbox_id)
+            .execute(pool)
+            .await?;
+            Err(e)
+        }
+    }
+}
+
+pub const NOTIFICATION_CHANNEL: &str = "notifications";
+
+// Payload of the `notifications` NOTIFY sent by the insert trigger. It is delivered on commit,
+// so streams fetch the row by `id` rather than by creation time.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct NotificationEvent {
+    pub id: Uuid,
+    pub vendor_id: Uuid,
+}
+
+// Fans Postgres notifications out to every open stream in this process
+#[derive(Clone)]
+pub struct NotificationHub {
+    sender: broadcast::Sender<NotificationEvent>,
+}
+
+impl NotificationHub {
+    pub fn subscribe(&self) -> broadcast::Receiver<NotificationEvent> {
+        self.sender.subscribe()
+    }
+}
+
+// One LISTEN connection per process; streams filter by vendor themselves
+pub fn spawn_listener(pool: PgPool) -> NotificationHub {
+    let (sender, _) = broadcast::channel(256);
+    let hub = NotificationHub {
+        sender: sender.clone(),
+    };
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = listen(&pool, &sender).await {
+                error!("Notification listener failed, reconnecting: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    });
+    hub
+}
+
+async fn listen(
+    pool: &PgPool,
+    sender: &broadcast::Sender<NotificationEvent>,
+) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(NOTIFICATION_CHANNEL).await?;
+    info!("Listening for notifications");
+    loop {
+        let notification = listener.recv().await?;
+        match serde_json::from_str::<NotificationEvent>(notification.payload()) {
+            // Sending only fails when nobody is subscribed, which is fine
+            Ok(event) => {
+                let _ = sender.send(event);
+            }
+            Err(e) => warn!("Ignoring malformed notification event: {}", e),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::i18n::{t, Locale, Message};
+use crate::models::*;
+
+pub struct RenderedMessage {
+    pub subject: String,
+    pub body: String,
+}
+
+fn catalog_id(kind: NotificationKind) -> &'static str {
+    match kind {
+        NotificationKind::General => "notification-general",
+        NotificationKind::VerificationPassed => "notification-verification-passed",
+        NotificationKind::VerificationFailed => "notification-verification-failed",
+        NotificationKind::ModerationApproved => "notification-moderation-approved",
+        NotificationKind::ModerationRejected => "notification-moderation-rejected",
+        NotificationKind::ProductSuspended => "notification-product-suspended",
+        NotificationKind::ProductReinstated => "notification-product-reinstated",
+    }
+}
+
+fn product_name(payload: &NotificationPayload, locale: Locale) -> String {
+    payload
+        .product_name
+        .clone()
+        .unwrap_or_else(|| t(locale, "product-fallback"))
+}
+
+// The in-app text for a notification; general notifications only have their stored message
+pub fn render_message(
+    kind: NotificationKind,
+    payload: &NotificationPayload,
+    stored: &str,
+    locale: Locale,
+) -> String {
+    let id = catalog_id(kind);
+    let message = match kind {
+        NotificationKind::General => return stored.to_string(),
+        NotificationKind::VerificationFailed if !payload.reasons.is_empty() => {
+            let reasons: Vec<String> = payload.reasons.iter().map(|r| r.render(locale)).collect();
+            Message::new(&format!("{}.with-reasons", id)).arg("reasons", reasons.join("; "))
+        }
+        NotificationKind::ModerationRejected | NotificationKind::ProductSuspended => {
+            match &payload.reason {
+                Some(reason) => {
+                    Message::new(&format!("{}.with-reason", id)).arg("reason", reason.as_str())
+                }
+                None => Message::new(id),
+            }
+        }
+        _ => Message::new(id),
+    };
+    message
+        .arg("product", product_name(payload, locale))
+        .render(locale)
+}
+
+// Replaces the stored message with one rendered from the current templates; rows migrated
+// from before typed kinds have no payload and keep the text they were written with
+pub fn rendered(mut notification: Notification, locale: Locale) -> Notification {
+    if notification.payload.product_name.is_none() {
+        return notification;
+    }
+    notification.message = render_message(
+        notification.kind,
+        &notification.payload,
+        &notification.message,
+        locale,
+    );
+    notification
+}
+
+pub fn render_email(
+    kind: NotificationKind,
+    vendor_name: &str,
+    payload: &NotificationPayload,
+    message: &str,
+    locale: Locale,
+) -> RenderedMessage {
+    let id = catalog_id(kind);
+    let product = product_name(payload, locale);
+    let subject = Message::new(&format!("{}.subject", id))
+        .arg("product", product)
+        .render(locale);
+    let body = format!(
+        "{}\n\n{}\n\n{}\n\n{}",
+        Message::new("email-greeting")
+            .arg("name", vendor_name)
+            .render(locale),
+        message,
+        t(locale, &format!("{}.next-step", id)),
+        t(locale, "email-signature")
+    );
+    RenderedMessage { subject, body }
+}
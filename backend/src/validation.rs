@@ -0,0 +1,465 @@
+use crate::auth::MIN_PASSWORD_LENGTH;
+use crate::errors::ApiError;
+use crate::i18n::{t, Locale, Message};
+use crate::models::*;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use url::Url;
+use uuid::Uuid;
+
+pub const MAX_NAME_LENGTH: usize = 255;
+pub const MAX_DESCRIPTION_LENGTH: usize = 5_000;
+pub const MAX_IMAGE_URL_LENGTH: usize = 2_048;
+pub const MAX_PRICE: Decimal = dec!(1000000000);
+pub const MAX_TAGS: usize = 10;
+pub const MAX_CATEGORIES: usize = 5;
+pub const MIN_WEBHOOK_SECRET_LENGTH: usize = 16;
+
+// Field name -> list of problems with that field, rendered as the `details.fields` of a 422
+#[derive(Debug, Default)]
+pub struct ValidationErrors {
+    locale: Locale,
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl ValidationErrors {
+    pub fn new(locale: Locale) -> Self {
+        ValidationErrors {
+            locale,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, field: &str, message: Message) {
+        self.fields
+            .entry(field.to_string())
+            .or_default()
+            .push(message.render(self.locale));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), ApiError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.into())
+        }
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        ApiError::Unprocessable {
+            message: t(errors.locale, "validation-failed"),
+            details: Some(json!({ "fields": errors.fields })),
+        }
+    }
+}
+
+// Checks that need no database access; referential checks live in the async helpers below
+pub trait Validate {
+    fn validate(&self, errors: &mut ValidationErrors);
+}
+
+impl Validate for NewProduct {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        let name_length = self.name.trim().chars().count();
+        if name_length == 0 {
+            errors.add("name", Message::new("validation-name-empty"));
+        } else if name_length > MAX_NAME_LENGTH {
+            errors.add(
+                "name",
+                Message::new("validation-name-too-long").arg("max", MAX_NAME_LENGTH),
+            );
+        }
+        if self.description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            errors.add(
+                "description",
+                Message::new("validation-description-too-long").arg("max", MAX_DESCRIPTION_LENGTH),
+            );
+        }
+        if self.price.is_sign_negative() && !self.price.is_zero() {
+            errors.add("price", Message::new("validation-price-invalid"));
+        } else if self.price > MAX_PRICE {
+            errors.add(
+                "price",
+                Message::new("validation-price-too-high").arg("max", MAX_PRICE.to_string()),
+            );
+        } else if self.price.normalize().scale() > self.currency.minor_units() {
+            errors.add(
+                "price",
+                Message::new("validation-price-precision")
+                    .arg("currency", self.currency.as_str())
+                    .arg("places", self.currency.minor_units()),
+            );
+        }
+        if let Err(id) = check_image_url(&self.image_url) {
+            errors.add("image_url", Message::new(id));
+        }
+        if let Some(category_ids) = &self.category_ids {
+            if self.all_category_ids().len() > MAX_CATEGORIES {
+                errors.add(
+                    "category_ids",
+                    Message::new("validation-too-many-categories").arg("max", MAX_CATEGORIES),
+                );
+            }
+            let mut seen = Vec::with_capacity(category_ids.len());
+            for category_id in category_ids {
+                if seen.contains(category_id) {
+                    errors.add(
+                        "category_ids",
+                        Message::new("validation-duplicate-category")
+                            .arg("id", category_id.to_string()),
+                    );
+                } else {
+                    seen.push(*category_id);
+                }
+            }
+        }
+        if let Some(tag_ids) = &self.tag_ids {
+            if tag_ids.len() > MAX_TAGS {
+                errors.add(
+                    "tag_ids",
+                    Message::new("validation-too-many-tags").arg("max", MAX_TAGS),
+                );
+            }
+            let mut seen = Vec::with_capacity(tag_ids.len());
+            for tag_id in tag_ids {
+                if seen.contains(tag_id) {
+                    errors.add(
+                        "tag_ids",
+                        Message::new("validation-duplicate-tag").arg("id", tag_id.to_string()),
+                    );
+                } else {
+                    seen.push(*tag_id);
+                }
+            }
+        }
+    }
+}
+
+impl Validate for NewVendor {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        let name_length = self.name.trim().chars().count();
+        if name_length == 0 {
+            errors.add("name", Message::new("validation-vendor-name-empty"));
+        } else if name_length > MAX_NAME_LENGTH {
+            errors.add(
+                "name",
+                Message::new("validation-name-too-long").arg("max", MAX_NAME_LENGTH),
+            );
+        }
+        let email = self.email.trim();
+        let valid_email = match email.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty() && domain.contains('.') && !domain.contains('@')
+            }
+            None => false,
+        };
+        if !valid_email || email.len() > MAX_NAME_LENGTH {
+            errors.add("email", Message::new("validation-email-invalid"));
+        }
+        if let Some(password) = &self.password {
+            if password.chars().count() < MIN_PASSWORD_LENGTH {
+                errors.add(
+                    "password",
+                    Message::new("validation-password-too-short").arg("min", MIN_PASSWORD_LENGTH),
+                );
+            }
+        }
+    }
+}
+
+impl Validate for NewWebhookSubscription {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        let url = self.url.trim();
+        let valid_url = url.len() <= MAX_IMAGE_URL_LENGTH
+            && matches!(
+                Url::parse(url),
+                Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some()
+            );
+        if !valid_url {
+            errors.add("url", Message::new("validation-webhook-url-invalid"));
+        }
+        if let Some(secret) = &self.secret {
+            if secret.chars().count() < MIN_WEBHOOK_SECRET_LENGTH {
+                errors.add(
+                    "secret",
+                    Message::new("validation-webhook-secret-too-short")
+                        .arg("min", MIN_WEBHOOK_SECRET_LENGTH),
+                );
+            }
+        }
+    }
+}
+
+fn check_taxonomy_name(name: &str, errors: &mut ValidationErrors) {
+    let name_length = name.trim().chars().count();
+    if name_length == 0 {
+        errors.add("name", Message::new("validation-name-empty"));
+    } else if name_length > MAX_NAME_LENGTH {
+        errors.add(
+            "name",
+            Message::new("validation-name-too-long").arg("max", MAX_NAME_LENGTH),
+        );
+    }
+}
+
+impl Validate for NewCategory {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        check_taxonomy_name(&self.name, errors);
+        if let Some(slug) = &self.slug {
+            let valid_slug = slug.len() <= MAX_NAME_LENGTH
+                && !slug.is_empty()
+                && slug.split('-').all(|part| {
+                    !part.is_empty() && part.bytes().all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9'))
+                });
+            if !valid_slug {
+                errors.add("slug", Message::new("validation-slug-invalid"));
+            } else if Uuid::parse_str(slug).is_ok() {
+                // Category lookups try the key as an id first, so such a slug could never be fetched
+                errors.add("slug", Message::new("validation-slug-uuid"));
+            }
+        }
+    }
+}
+
+impl Validate for NewTag {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        check_taxonomy_name(&self.name, errors);
+    }
+}
+
+// Empty is allowed for drafts; otherwise either an uploaded file path or an absolute http(s) URL.
+// Errors are catalog ids.
+fn check_image_url(image_url: &str) -> Result<(), &'static str> {
+    let image_url = image_url.trim();
+    if image_url.is_empty() {
+        return Ok(());
+    }
+    if image_url.len() > MAX_IMAGE_URL_LENGTH {
+        return Err("validation-image-url-too-long");
+    }
+    if let Some(file) = image_url.strip_prefix("/uploads/") {
+        if file.is_empty() || file.contains("..") || file.contains('/') {
+            return Err("validation-image-url-not-uploaded");
+        }
+        return Ok(());
+    }
+    match Url::parse(image_url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => Ok(()),
+        _ => Err("validation-image-url-invalid"),
+    }
+}
+
+pub async fn validate_product(
+    pool: &PgPool,
+    product: &NewProduct,
+    locale: Locale,
+) -> Result<(), ApiError> {
+    let mut errors = ValidationErrors::new(locale);
+    product.validate(&mut errors);
+    if let Some(category_id) = product.category_id {
+        check_category_exists(pool, category_id, "category_id", &mut errors).await?;
+    }
+    for category_id in product.category_ids.iter().flatten() {
+        check_category_exists(pool, *category_id, "category_ids", &mut errors).await?;
+    }
+    if let Some(tag_ids) = &product.tag_ids {
+        check_tags_exist(pool, tag_ids, &mut errors).await?;
+    }
+    errors.into_result()
+}
+
+pub async fn validate_category(
+    pool: &PgPool,
+    category: &NewCategory,
+    locale: Locale,
+) -> Result<(), ApiError> {
+    let mut errors = ValidationErrors::new(locale);
+    category.validate(&mut errors);
+    if let Some(parent_id) = category.parent() {
+        check_category_exists(pool, parent_id, "parent_id", &mut errors).await?;
+    }
+    errors.into_result()
+}
+
+async fn check_category_exists(
+    pool: &PgPool,
+    category_id: Uuid,
+    field: &str,
+    errors: &mut ValidationErrors,
+) -> Result<(), sqlx::Error> {
+    let exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM categories WHERE id = $1)")
+            .bind(category_id)
+            .fetch_one(pool)
+            .await?;
+    if !exists {
+        errors.add(
+            field,
+            Message::new("validation-unknown-category").arg("id", category_id.to_string()),
+        );
+    }
+    Ok(())
+}
+
+// Pending proposals count as unknown until a moderator approves them
+async fn check_tags_exist(
+    pool: &PgPool,
+    tag_ids: &[Uuid],
+    errors: &mut ValidationErrors,
+) -> Result<(), sqlx::Error> {
+    if tag_ids.is_empty() {
+        return Ok(());
+    }
+    let known = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM tags WHERE id = ANY($1) AND status = 'approved'",
+    )
+    .bind(tag_ids)
+    .fetch_all(pool)
+    .await?;
+    for tag_id in tag_ids.iter().filter(|id| !known.contains(id)) {
+        errors.add(
+            "tag_ids",
+            Message::new("validation-unknown-tag").arg("id", tag_id.to_string()),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(price: serde_json::Value) -> NewProduct {
+        serde_json::from_value(json!({
+            "name": "Ndop cloth",
+            "description": "Hand dyed",
+            "price": price,
+            "image_url": "/uploads/ndop.jpg",
+            "category_id": null,
+            "tag_ids": null,
+        }))
+        .unwrap()
+    }
+
+    fn priced(price: &str, currency: Currency) -> NewProduct {
+        NewProduct {
+            currency,
+            ..product(json!(price))
+        }
+    }
+
+    fn failed_fields(value: &impl Validate) -> Vec<String> {
+        let mut errors = ValidationErrors::new(Locale::En);
+        value.validate(&mut errors);
+        errors.fields.into_keys().collect()
+    }
+
+    #[test]
+    fn product_rejects_blank_names_and_duplicate_tags() {
+        let mut new_product = product(json!("100"));
+        assert!(failed_fields(&new_product).is_empty());
+        new_product.name = "   ".to_string();
+        let tag_id = Uuid::new_v4();
+        new_product.tag_ids = Some(vec![tag_id, tag_id]);
+        assert_eq!(failed_fields(&new_product), ["name", "tag_ids"]);
+    }
+
+    #[test]
+    fn product_price_must_be_non_negative_and_bounded() {
+        assert!(failed_fields(&product(json!("0"))).is_empty());
+        assert_eq!(failed_fields(&product(json!("-1"))), ["price"]);
+        assert_eq!(failed_fields(&product(json!("1000000001"))), ["price"]);
+    }
+
+    #[test]
+    fn image_url_accepts_uploads_and_web_urls() {
+        assert_eq!(check_image_url(""), Ok(()));
+        assert_eq!(check_image_url("/uploads/a.png"), Ok(()));
+        assert_eq!(check_image_url("https://cdn.example.com/a.png"), Ok(()));
+        assert_eq!(
+            check_image_url("/uploads/../secret"),
+            Err("validation-image-url-not-uploaded")
+        );
+        assert_eq!(
+            check_image_url("/uploads/a/b.png"),
+            Err("validation-image-url-not-uploaded")
+        );
+        assert_eq!(
+            check_image_url("javascript:alert(1)"),
+            Err("validation-image-url-invalid")
+        );
+        assert_eq!(
+            check_image_url(&format!(
+                "https://e.com/{}",
+                "a".repeat(MAX_IMAGE_URL_LENGTH)
+            )),
+            Err("validation-image-url-too-long")
+        );
+    }
+
+    #[test]
+    fn vendor_needs_an_email_and_a_long_enough_password() {
+        let vendor = |email: &str, password: &str| NewVendor {
+            name: "Kribi Weavers".to_string(),
+            email: email.to_string(),
+            password: Some(password.to_string()),
+            locale: None,
+            current_password: None,
+        };
+        assert!(failed_fields(&vendor("weavers@example.com", "long enough")).is_empty());
+        assert_eq!(failed_fields(&vendor("weavers@", "long enough")), ["email"]);
+        assert_eq!(
+            failed_fields(&vendor("weavers.example.com", "short")),
+            ["email", "password"]
+        );
+    }
+
+    #[test]
+    fn category_slug_must_be_dashed_lowercase_words() {
+        let category = |slug: &str| NewCategory {
+            name: "Bags".to_string(),
+            parent_id: None,
+            slug: Some(slug.to_string()),
+            position: None,
+        };
+        assert!(failed_fields(&category("leather-bags-2")).is_empty());
+        for slug in [
+            "",
+            "Bags",
+            "bags-",
+            "-bags",
+            "bags--2",
+            "sacs_cuir",
+            "550e8400-e29b-41d4-a716-446655440000",
+            "550e8400e29b41d4a716446655440000",
+        ] {
+            assert_eq!(failed_fields(&category(slug)), ["slug"], "{}", slug);
+        }
+    }
+
+    #[test]
+    fn price_parses_strings_exactly() {
+        assert_eq!(product(json!("12500")).price, dec!(12500));
+        assert_eq!(product(json!("19.99")).price, dec!(19.99));
+        assert_eq!(product(json!(42)).price, dec!(42));
+        assert_eq!(product(json!("19.99")).currency, Currency::Xaf);
+    }
+
+    #[test]
+    fn price_precision_follows_the_currency() {
+        assert!(failed_fields(&priced("19.99", Currency::Eur)).is_empty());
+        assert!(failed_fields(&priced("19.90", Currency::Ngn)).is_empty());
+        assert!(failed_fields(&priced("1500.00", Currency::Xaf)).is_empty());
+        assert_eq!(failed_fields(&priced("1500.5", Currency::Xaf)), ["price"]);
+        assert_eq!(failed_fields(&priced("1.999", Currency::Usd)), ["price"]);
+    }
+}
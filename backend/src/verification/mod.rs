@@ -0,0 +1,235 @@
+pub mod rules;
+
+use crate::errors::ApiError;
+use crate::i18n::{Locale, Message};
+use crate::lifecycle;
+use crate::models::*;
+use crate::notifications::notify_product_vendor;
+use log::{info, warn};
+use rules::{build_rules, ProductFacts, RuleConfig, VerificationRule};
+use rust_decimal::Decimal;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::path::Path;
+use uuid::Uuid;
+
+#[derive(Debug, FromRow)]
+struct ProductRow {
+    name: String,
+    description: String,
+    price: Decimal,
+    currency: Currency,
+    image_url: String,
+    tag_count: i64,
+}
+
+#[derive(Debug, FromRow)]
+struct ConfiguredRule {
+    category_id: Option<Uuid>,
+    config: Json<RuleConfig>,
+}
+
+async fn load_facts(pool: &PgPool, product_id: Uuid) -> Result<Option<ProductFacts>, sqlx::Error> {
+    let Some(row) = sqlx::query_as::<_, ProductRow>(
+        r#"
+        SELECT p.name, p.description, p.price, p.currency, p.image_url,
+            (SELECT COUNT(*) FROM product_tags WHERE product_id = p.id) as tag_count
+        FROM products p
+        WHERE p.id = $1
+        "#,
+    )
+    .bind(product_id)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+    let category_ids = sqlx::query_scalar::<_, Uuid>(
+        "SELECT category_id FROM product_categories WHERE product_id = $1",
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+    let image_dimensions = uploaded_image_dimensions(&row.image_url).await;
+    Ok(Some(ProductFacts {
+        name: row.name,
+        description: row.description,
+        price: row.price,
+        currency: row.currency,
+        image_url: row.image_url,
+        category_ids,
+        tag_count: row.tag_count,
+        image_dimensions,
+    }))
+}
+
+// Only files served from /uploads can be inspected; decoding runs off the async executor
+async fn uploaded_image_dimensions(image_url: &str) -> Option<Result<(u32, u32), String>> {
+    let file = image_url.trim().strip_prefix("/uploads/")?;
+    let path = Path::new("uploads").join(file);
+    let dimensions = tokio::task::spawn_blocking(move || image::image_dimensions(path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()));
+    Some(dimensions)
+}
+
+// Global rules plus the product's category rules; a category rule overrides a global rule of the same kind
+async fn load_rules(
+    pool: &PgPool,
+    category_ids: &[Uuid],
+) -> Result<Vec<Box<dyn VerificationRule>>, sqlx::Error> {
+    let configured = sqlx::query_as::<_, ConfiguredRule>(
+        r#"
+        SELECT category_id, config FROM verification_rules
+        WHERE enabled AND (category_id IS NULL OR category_id = ANY($1))
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(category_ids)
+    .fetch_all(pool)
+    .await?;
+    if configured.is_empty() {
+        warn!("No verification rules configured, falling back to defaults");
+        return Ok(build_rules(RuleConfig::defaults()));
+    }
+    let overridden: Vec<_> = configured
+        .iter()
+        .filter(|r| r.category_id.is_some())
+        .map(|r| r.config.scope())
+        .collect();
+    Ok(build_rules(
+        configured
+            .into_iter()
+            .filter(|r| r.category_id.is_some() || !overridden.contains(&r.config.scope()))
+            .map(|r| r.config.0),
+    ))
+}
+
+// Runs every rule, stores the report, updates the product and notifies the vendor.
+// Returns None when the product left `submitted` before the check got to run.
+pub async fn verify_product(
+    pool: &PgPool,
+    product_id: Uuid,
+) -> Result<Option<VerificationReport>, ApiError> {
+    // The product may have been deleted while the job waited; there is nothing left to verify
+    let Some(facts) = load_facts(pool, product_id).await? else {
+        info!(
+            "Skipping verification of product {}: it no longer exists",
+            product_id
+        );
+        return Ok(None);
+    };
+    let rules: Vec<RuleResult> = load_rules(pool, &facts.category_ids)
+        .await?
+        .iter()
+        .flat_map(|rule| rule.check(&facts))
+        .collect();
+    let passed = rules.iter().all(|r| r.passed);
+
+    let mut tx = pool.begin().await?;
+    let status = sqlx::query_scalar::<_, ProductStatus>(
+        "SELECT status FROM products WHERE id = $1 FOR UPDATE",
+    )
+    .bind(product_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(status) = status else {
+        info!(
+            "Skipping verification of product {}: it no longer exists",
+            product_id
+        );
+        return Ok(None);
+    };
+    if status != ProductStatus::Submitted {
+        info!(
+            "Skipping verification of product {}: it is {}",
+            product_id,
+            status.as_str()
+        );
+        return Ok(None);
+    }
+    let report = sqlx::query_as::<_, VerificationReport>(
+        r#"
+        INSERT INTO verification_reports (product_id, passed, rules)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(product_id)
+    .bind(passed)
+    .bind(Json(&rules))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Passing products wait for a moderator; only failures are decided here
+    let next = if passed {
+        ProductStatus::InReview
+    } else {
+        ProductStatus::Rejected
+    };
+    lifecycle::transition(&mut tx, product_id, next, None, None).await?;
+
+    let kind = if passed {
+        NotificationKind::VerificationPassed
+    } else {
+        NotificationKind::VerificationFailed
+    };
+    let payload = NotificationPayload {
+        reasons: rules
+            .iter()
+            .filter(|r| !r.passed)
+            .map(|r| {
+                r.message
+                    .clone()
+                    .unwrap_or_else(|| Message::Text(r.reason.clone()))
+            })
+            .collect(),
+        ..Default::default()
+    };
+    notify_product_vendor(&mut tx, product_id, kind, payload).await?;
+    tx.commit().await?;
+
+    info!(
+        "Verified product {}: {}",
+        product_id,
+        if passed { "passed" } else { "failed" }
+    );
+    Ok(Some(report))
+}
+
+// Rule reasons are rendered in `locale`
+pub async fn latest_report(
+    pool: &PgPool,
+    product_id: Uuid,
+    locale: Locale,
+) -> Result<Option<VerificationReport>, sqlx::Error> {
+    let report = sqlx::query_as::<_, VerificationReport>(
+        r#"
+        SELECT * FROM verification_reports
+        WHERE product_id = $1
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(product_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(report.map(|mut report| {
+        for rule in report.rules.iter_mut() {
+            rule.localize(locale);
+        }
+        report
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn deleted_products_are_skipped(pool: PgPool) {
+        let report = verify_product(&pool, Uuid::new_v4()).await.unwrap();
+        assert!(report.is_none());
+    }
+}
@@ -0,0 +1,533 @@
+use crate::i18n::{Locale, Message};
+use crate::models::{Currency, RuleResult};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Everything the rules look at, gathered once per verification run
+#[derive(Debug)]
+pub struct ProductFacts {
+    pub name: String,
+    pub description: String,
+    pub price: Decimal,
+    pub currency: Currency,
+    pub image_url: String,
+    pub category_ids: Vec<Uuid>,
+    pub tag_count: i64,
+    // None when the image is not an uploaded file and so cannot be inspected
+    pub image_dimensions: Option<Result<(u32, u32), String>>,
+}
+
+pub trait VerificationRule: Send + Sync {
+    // A rule may report several results, e.g. one per required field
+    fn check(&self, product: &ProductFacts) -> Vec<RuleResult>;
+}
+
+// Reports are stored in English and re-rendered in the reader's language
+fn result(rule: &str, passed: bool, message: Message) -> RuleResult {
+    RuleResult {
+        rule: rule.to_string(),
+        passed,
+        reason: message.render(Locale::En),
+        message: Some(message),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequiredField {
+    Name,
+    Description,
+    Image,
+    Category,
+    Tag,
+}
+
+pub struct RequiredFields {
+    pub fields: Vec<RequiredField>,
+}
+
+impl VerificationRule for RequiredFields {
+    fn check(&self, p: &ProductFacts) -> Vec<RuleResult> {
+        self.fields
+            .iter()
+            .map(|field| match field {
+                RequiredField::Name => {
+                    let passed = !p.name.trim().is_empty();
+                    result(
+                        "name_present",
+                        passed,
+                        Message::new(if passed {
+                            "rule-name-present"
+                        } else {
+                            "rule-name-missing"
+                        }),
+                    )
+                }
+                RequiredField::Description => {
+                    let passed = !p.description.trim().is_empty();
+                    result(
+                        "description_present",
+                        passed,
+                        Message::new(if passed {
+                            "rule-description-present"
+                        } else {
+                            "rule-description-missing"
+                        }),
+                    )
+                }
+                RequiredField::Image => {
+                    let passed = !p.image_url.trim().is_empty();
+                    result(
+                        "image_present",
+                        passed,
+                        Message::new(if passed {
+                            "rule-image-present"
+                        } else {
+                            "rule-image-missing"
+                        }),
+                    )
+                }
+                RequiredField::Category => {
+                    let passed = !p.category_ids.is_empty();
+                    result(
+                        "category_assigned",
+                        passed,
+                        Message::new(if passed {
+                            "rule-category-assigned"
+                        } else {
+                            "rule-category-missing"
+                        }),
+                    )
+                }
+                RequiredField::Tag => {
+                    let passed = p.tag_count > 0;
+                    result(
+                        "tag_assigned",
+                        passed,
+                        Message::new(if passed {
+                            "rule-tag-assigned"
+                        } else {
+                            "rule-tag-missing"
+                        }),
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+// Bounds are in `currency`
+#[derive(Debug, Clone, Copy)]
+pub struct PriceRange {
+    pub min: Decimal,
+    pub max: Decimal,
+    pub currency: Currency,
+}
+
+// Every price range that applies to the product; a currency without a range fails rather than going unchecked
+pub struct PriceRanges {
+    pub ranges: Vec<PriceRange>,
+}
+
+impl VerificationRule for PriceRanges {
+    fn check(&self, p: &ProductFacts) -> Vec<RuleResult> {
+        let Some(range) = self.ranges.iter().find(|r| r.currency == p.currency) else {
+            return vec![result(
+                "price_in_range",
+                false,
+                Message::new("rule-price-no-range").arg("currency", p.currency.as_str()),
+            )];
+        };
+        let passed = p.price >= range.min && p.price <= range.max;
+        let amount = |value: Decimal| format!("{} {}", value.normalize(), range.currency.as_str());
+        let reason = if passed {
+            Message::new("rule-price-in-range")
+        } else {
+            Message::new("rule-price-out-of-range")
+                .arg("price", amount(p.price))
+                .arg("min", amount(range.min))
+                .arg("max", amount(range.max))
+        };
+        vec![result("price_in_range", passed, reason)]
+    }
+}
+
+pub struct DescriptionLength {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl VerificationRule for DescriptionLength {
+    fn check(&self, p: &ProductFacts) -> Vec<RuleResult> {
+        let length = p.description.trim().chars().count();
+        let (passed, reason) = if length < self.min {
+            (
+                false,
+                Message::new("rule-description-too-short")
+                    .arg("min", self.min)
+                    .arg("length", length),
+            )
+        } else if self.max.is_some_and(|max| length > max) {
+            (
+                false,
+                Message::new("rule-description-too-long")
+                    .arg("max", self.max.unwrap_or_default())
+                    .arg("length", length),
+            )
+        } else {
+            (true, Message::new("rule-description-length-ok"))
+        };
+        vec![result("description_length", passed, reason)]
+    }
+}
+
+pub struct ImageDimensions {
+    pub min_width: u32,
+    pub min_height: u32,
+}
+
+impl VerificationRule for ImageDimensions {
+    fn check(&self, p: &ProductFacts) -> Vec<RuleResult> {
+        let (passed, reason) = match &p.image_dimensions {
+            Some(Ok((width, height))) if *width >= self.min_width && *height >= self.min_height => {
+                (
+                    true,
+                    Message::new("rule-image-size-ok")
+                        .arg("width", *width)
+                        .arg("height", *height),
+                )
+            }
+            Some(Ok((width, height))) => (
+                false,
+                Message::new("rule-image-too-small")
+                    .arg("width", *width)
+                    .arg("height", *height)
+                    .arg("min_width", self.min_width)
+                    .arg("min_height", self.min_height),
+            ),
+            Some(Err(e)) => (
+                false,
+                Message::new("rule-image-unreadable").arg("error", e.as_str()),
+            ),
+            None => (true, Message::new("rule-image-not-checked")),
+        };
+        vec![result("image_dimensions", passed, reason)]
+    }
+}
+
+pub struct BannedWords {
+    pub words: Vec<String>,
+}
+
+impl VerificationRule for BannedWords {
+    fn check(&self, p: &ProductFacts) -> Vec<RuleResult> {
+        let text = format!("{} {}", p.name, p.description).to_lowercase();
+        let words: Vec<&str> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        let found: Vec<&str> = self
+            .words
+            .iter()
+            .map(String::as_str)
+            .filter(|banned| words.contains(&banned.to_lowercase().as_str()))
+            .collect();
+        if found.is_empty() {
+            vec![result(
+                "banned_words",
+                true,
+                Message::new("rule-no-banned-words"),
+            )]
+        } else {
+            vec![result(
+                "banned_words",
+                false,
+                Message::new("rule-banned-words").arg("words", found.join(", ")),
+            )]
+        }
+    }
+}
+
+// Serialized form stored in `verification_rules.config`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleConfig {
+    RequiredFields {
+        fields: Vec<RequiredField>,
+    },
+    PriceRange {
+        min: Decimal,
+        max: Decimal,
+        // Ranges configured before prices had a currency were in XAF
+        #[serde(default)]
+        currency: Currency,
+    },
+    DescriptionLength {
+        min: usize,
+        max: Option<usize>,
+    },
+    ImageDimensions {
+        min_width: u32,
+        min_height: u32,
+    },
+    BannedWords {
+        words: Vec<String>,
+    },
+}
+
+impl RuleConfig {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuleConfig::RequiredFields { .. } => "required_fields",
+            RuleConfig::PriceRange { .. } => "price_range",
+            RuleConfig::DescriptionLength { .. } => "description_length",
+            RuleConfig::ImageDimensions { .. } => "image_dimensions",
+            RuleConfig::BannedWords { .. } => "banned_words",
+        }
+    }
+
+    // What a category rule overrides: the global rule of the same kind, and for price ranges the same currency
+    pub fn scope(&self) -> (&'static str, Option<Currency>) {
+        match self {
+            RuleConfig::PriceRange { currency, .. } => (self.kind(), Some(*currency)),
+            _ => (self.kind(), None),
+        }
+    }
+
+    pub fn build(self) -> Box<dyn VerificationRule> {
+        match self {
+            RuleConfig::RequiredFields { fields } => Box::new(RequiredFields { fields }),
+            RuleConfig::PriceRange { min, max, currency } => Box::new(PriceRanges {
+                ranges: vec![PriceRange { min, max, currency }],
+            }),
+            RuleConfig::DescriptionLength { min, max } => Box::new(DescriptionLength { min, max }),
+            RuleConfig::ImageDimensions {
+                min_width,
+                min_height,
+            } => Box::new(ImageDimensions {
+                min_width,
+                min_height,
+            }),
+            RuleConfig::BannedWords { words } => Box::new(BannedWords { words }),
+        }
+    }
+
+    // Used when no rules are configured at all, matching the original hard-coded checks
+    pub fn defaults() -> Vec<RuleConfig> {
+        let mut defaults = vec![RuleConfig::RequiredFields {
+            fields: vec![
+                RequiredField::Name,
+                RequiredField::Description,
+                RequiredField::Image,
+                RequiredField::Category,
+                RequiredField::Tag,
+            ],
+        }];
+        // Same bounds as migration 0019 seeds
+        defaults.extend(Currency::ALL.into_iter().map(|currency| {
+            let (min, max) = match currency {
+                Currency::Xaf | Currency::Xof | Currency::Ngn => (dec!(500), dec!(1000000000)),
+                Currency::Eur | Currency::Usd => (dec!(1), dec!(1500000)),
+            };
+            RuleConfig::PriceRange { min, max, currency }
+        }));
+        defaults
+    }
+}
+
+// Price ranges are merged into a single rule, in the place of the first one, so it can tell
+// "no range for this currency" apart from "range for another currency"
+pub fn build_rules(
+    configs: impl IntoIterator<Item = RuleConfig>,
+) -> Vec<Box<dyn VerificationRule>> {
+    let mut rules: Vec<Box<dyn VerificationRule>> = Vec::new();
+    let mut price_ranges: Option<(usize, Vec<PriceRange>)> = None;
+    for config in configs {
+        match config {
+            RuleConfig::PriceRange { min, max, currency } => {
+                let range = PriceRange { min, max, currency };
+                match &mut price_ranges {
+                    Some((_, ranges)) => ranges.push(range),
+                    None => price_ranges = Some((rules.len(), vec![range])),
+                }
+            }
+            other => rules.push(other.build()),
+        }
+    }
+    if let Some((position, ranges)) = price_ranges {
+        rules.insert(position, Box::new(PriceRanges { ranges }));
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn priced(price: Decimal, currency: Currency) -> ProductFacts {
+        ProductFacts {
+            price,
+            currency,
+            ..facts()
+        }
+    }
+
+    fn price_results(
+        rules: &[Box<dyn VerificationRule>],
+        product: &ProductFacts,
+    ) -> Vec<RuleResult> {
+        rules
+            .iter()
+            .flat_map(|rule| rule.check(product))
+            .filter(|result| result.rule == "price_in_range")
+            .collect()
+    }
+
+    fn facts() -> ProductFacts {
+        ProductFacts {
+            name: "Ndop cloth".to_string(),
+            description: "Hand dyed cotton from the Grassfields".to_string(),
+            price: dec!(1000),
+            currency: Currency::Xaf,
+            image_url: "/uploads/ndop.jpg".to_string(),
+            category_ids: vec![Uuid::new_v4()],
+            tag_count: 1,
+            image_dimensions: None,
+        }
+    }
+
+    #[test]
+    fn required_fields_report_each_missing_field() {
+        let rule = RequiredFields {
+            fields: vec![RequiredField::Name, RequiredField::Tag],
+        };
+        let mut product = facts();
+        assert!(rule.check(&product).iter().all(|r| r.passed));
+        product.name = "  ".to_string();
+        product.tag_count = 0;
+        let results = rule.check(&product);
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.rule.as_str(), r.passed))
+                .collect::<Vec<_>>(),
+            [("name_present", false), ("tag_assigned", false)]
+        );
+    }
+
+    #[test]
+    fn description_length_checks_both_bounds() {
+        let rule = DescriptionLength {
+            min: 10,
+            max: Some(40),
+        };
+        let mut product = facts();
+        assert!(rule.check(&product)[0].passed);
+        product.description = "  Short   ".to_string();
+        assert!(!rule.check(&product)[0].passed);
+        product.description = "x".repeat(41);
+        assert!(!rule.check(&product)[0].passed);
+    }
+
+    #[test]
+    fn image_dimensions_skip_images_that_cannot_be_read() {
+        let rule = ImageDimensions {
+            min_width: 400,
+            min_height: 400,
+        };
+        let mut product = facts();
+        assert!(rule.check(&product)[0].passed);
+        product.image_dimensions = Some(Ok((300, 500)));
+        assert!(!rule.check(&product)[0].passed);
+        product.image_dimensions = Some(Err("truncated file".to_string()));
+        assert!(!rule.check(&product)[0].passed);
+    }
+
+    #[test]
+    fn banned_words_match_whole_words_ignoring_case() {
+        let rule = BannedWords {
+            words: vec!["Replica".to_string()],
+        };
+        let mut product = facts();
+        product.name = "REPLICA handbag".to_string();
+        assert!(!rule.check(&product)[0].passed);
+        product.name = "Replicas of masks".to_string();
+        assert!(rule.check(&product)[0].passed);
+    }
+
+    #[test]
+    fn rule_config_round_trips_through_json() {
+        let config: RuleConfig =
+            serde_json::from_str(r#"{"kind": "banned_words", "words": ["fake"]}"#).unwrap();
+        assert_eq!(config.kind(), "banned_words");
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["kind"], "banned_words");
+        assert!(serde_json::from_str::<RuleConfig>(r#"{"kind": "unknown"}"#).is_err());
+    }
+
+    #[test]
+    fn price_ranges_use_the_range_of_the_product_currency() {
+        let rule = PriceRanges {
+            ranges: vec![
+                PriceRange {
+                    min: dec!(500),
+                    max: dec!(1000000),
+                    currency: Currency::Xaf,
+                },
+                PriceRange {
+                    min: dec!(1),
+                    max: dec!(1500),
+                    currency: Currency::Eur,
+                },
+            ],
+        };
+        assert!(rule.check(&priced(dec!(25), Currency::Eur))[0].passed);
+        assert!(rule.check(&priced(dec!(500), Currency::Xaf))[0].passed);
+        // 25 would be below the XAF minimum and 5000 above the EUR maximum
+        assert!(!rule.check(&priced(dec!(25), Currency::Xaf))[0].passed);
+        let failed = &rule.check(&priced(dec!(5000), Currency::Eur))[0];
+        assert!(!failed.passed);
+        assert_eq!(
+            failed.reason,
+            "Price 5000 EUR must be between 1 EUR and 1500 EUR"
+        );
+    }
+
+    #[test]
+    fn price_ranges_fail_currencies_without_a_range() {
+        let rule = PriceRanges {
+            ranges: vec![PriceRange {
+                min: dec!(500),
+                max: dec!(1000000),
+                currency: Currency::Xaf,
+            }],
+        };
+        let results = rule.check(&priced(dec!(0), Currency::Eur));
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].passed);
+        assert_eq!(
+            results[0].message,
+            Some(Message::new("rule-price-no-range").arg("currency", "EUR"))
+        );
+    }
+
+    #[test]
+    fn build_rules_merges_price_ranges_into_one_rule() {
+        let rules = build_rules(RuleConfig::defaults());
+        assert_eq!(rules.len(), 2);
+        for currency in Currency::ALL {
+            let results = price_results(&rules, &priced(dec!(1000), currency));
+            assert_eq!(results.len(), 1, "{:?}", currency);
+            assert!(results[0].passed, "{:?}", currency);
+        }
+    }
+
+    #[test]
+    fn legacy_price_range_config_is_in_xaf() {
+        let config: RuleConfig =
+            serde_json::from_str(r#"{"kind": "price_range", "min": 500, "max": 1000}"#).unwrap();
+        assert_eq!(config.scope(), ("price_range", Some(Currency::Xaf)));
+    }
+}
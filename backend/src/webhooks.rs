@@ -0,0 +1,345 @@
+use crate::i18n::{Locale, Message};
+use crate::jobs::{self, Task};
+use crate::models::*;
+use anyhow::bail;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use log::info;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgConnection, PgPool};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::lookup_host;
+use url::{Host, Url};
+use uuid::Uuid;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+// Signs `<timestamp>.<body>` so receivers can reject replayed deliveries
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// Webhook targets must be on the public internet; anything else would let a vendor make the
+// server call into its own network (loopback, RFC 1918, link-local cloud metadata, ...)
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                // Shared address space (carrier-grade NAT)
+                || (a == 100 && (64..128).contains(&b))
+                // IETF protocol assignments
+                || (a == 192 && b == 0 && c == 0)
+                // Benchmarking
+                || (a == 198 && (18..20).contains(&b))
+                // Reserved
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+            let [first, second, ..] = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local
+                || (first & 0xfe00) == 0xfc00
+                // Link-local
+                || (first & 0xffc0) == 0xfe80
+                // Documentation
+                || (first == 0x2001 && second == 0x0db8))
+        }
+    }
+}
+
+// Resolves the URL's host and fails unless every address is public. Errors are catalog ids.
+pub async fn check_target(url: &str) -> Result<(), &'static str> {
+    let url = Url::parse(url.trim()).map_err(|_| "validation-webhook-url-invalid")?;
+    let addresses: Vec<IpAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![ip.into()],
+        Some(Host::Ipv6(ip)) => vec![ip.into()],
+        Some(Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            lookup_host((domain, port))
+                .await
+                .map_err(|_| "validation-webhook-url-unresolvable")?
+                .map(|address| address.ip())
+                .collect()
+        }
+        None => return Err("validation-webhook-url-invalid"),
+    };
+    if addresses.is_empty() {
+        Err("validation-webhook-url-unresolvable")
+    } else if addresses.into_iter().all(is_public_ip) {
+        Ok(())
+    } else {
+        Err("validation-webhook-url-private")
+    }
+}
+
+// Drops non-public addresses at connect time, so a hostname re-pointed at an internal address
+// after the subscription was checked still cannot be reached
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public_ip(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+// Redirects are not followed: a public endpoint could otherwise bounce the request inward
+pub fn http_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+}
+
+// Queues a delivery to every matching subscription; runs on the caller's connection so events
+// are only sent for changes that commit
+pub async fn emit(
+    conn: &mut PgConnection,
+    event: WebhookEvent,
+    vendor_id: Uuid,
+    data: &impl Serialize,
+) -> Result<(), sqlx::Error> {
+    let subscriptions = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM webhook_subscriptions
+        WHERE active
+            AND (vendor_id = $1 OR vendor_id IS NULL)
+            AND (cardinality(events) = 0 OR $2 = ANY(events))
+        "#,
+    )
+    .bind(vendor_id)
+    .bind(event)
+    .fetch_all(&mut *conn)
+    .await?;
+    let occurred_at = Utc::now();
+    for subscription_id in subscriptions {
+        let delivery_id = Uuid::new_v4();
+        let payload = json!({
+            "id": delivery_id,
+            "event": event,
+            "occurred_at": occurred_at,
+            "data": data,
+        });
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (id, subscription_id, event, payload)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(delivery_id)
+        .bind(subscription_id)
+        .bind(event)
+        .bind(Json(payload))
+        .execute(&mut *conn)
+        .await?;
+        jobs::enqueue(&mut *conn, &Task::DeliverWebhook { delivery_id }, None).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, FromRow)]
+struct PendingDelivery {
+    event: WebhookEvent,
+    payload: Json<serde_json::Value>,
+    delivered_at: Option<chrono::DateTime<Utc>>,
+    url: String,
+    secret: String,
+    active: bool,
+}
+
+// Makes one attempt and records it; an error hands the retry over to the job runner
+pub async fn deliver(
+    pool: &PgPool,
+    http: &reqwest::Client,
+    delivery_id: Uuid,
+) -> anyhow::Result<()> {
+    let delivery = sqlx::query_as::<_, PendingDelivery>(
+        r#"
+        SELECT d.event, d.payload, d.delivered_at, s.url, s.secret, s.active
+        FROM webhook_deliveries d
+        JOIN webhook_subscriptions s ON s.id = d.subscription_id
+        WHERE d.id = $1
+        "#,
+    )
+    .bind(delivery_id)
+    .fetch_optional(pool)
+    .await?;
+    // Gone with its subscription, already delivered, or paused since it was queued
+    let Some(delivery) = delivery else {
+        return Ok(());
+    };
+    if delivery.delivered_at.is_some() || !delivery.active {
+        return Ok(());
+    }
+
+    let body = serde_json::to_string(&delivery.payload.0)?;
+    let timestamp = Utc::now().timestamp();
+    let started = Instant::now();
+    // The resolver only covers hostnames; IP literals are checked here
+    let (status_code, error) = match check_target(&delivery.url).await {
+        Err(id) => (None, Some(Message::new(id).render(Locale::En))),
+        Ok(()) => {
+            let response = http
+                .post(&delivery.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, delivery.event.as_str())
+                .header(DELIVERY_HEADER, delivery_id.to_string())
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, sign(&delivery.secret, timestamp, &body))
+                .body(body)
+                .send()
+                .await;
+            match response {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16()), None)
+                }
+                Ok(response) => (
+                    Some(response.status().as_u16()),
+                    Some(format!("Endpoint responded with {}", response.status())),
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            }
+        }
+    };
+    let duration_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+    let status_code = status_code.map(i32::from);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO webhook_delivery_attempts (delivery_id, status_code, error, duration_ms)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(delivery_id)
+    .bind(status_code)
+    .bind(&error)
+    .bind(duration_ms)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET attempts = attempts + 1, last_status = $1, last_error = $2,
+            delivered_at = CASE WHEN $2 IS NULL THEN NOW() END
+        WHERE id = $3
+        "#,
+    )
+    .bind(status_code)
+    .bind(&error)
+    .bind(delivery_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    if let Some(error) = error {
+        bail!("Webhook delivery to {} failed: {}", delivery.url, error);
+    }
+    info!(
+        "Delivered {} webhook {} to {}",
+        delivery.event.as_str(),
+        delivery_id,
+        delivery.url
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_matches_a_known_hmac_sha256() {
+        // python3: hmac.new(b"whsec_test", b'1700000000.{"event":"product.live"}', hashlib.sha256)
+        assert_eq!(
+            sign("whsec_test", 1_700_000_000, r#"{"event":"product.live"}"#),
+            "sha256=56ea9de0452fd516c7c7e2fecfe3aea620191e4187926f964c889a2cd3660072"
+        );
+    }
+
+    #[test]
+    fn sign_covers_the_timestamp() {
+        let body = r#"{"event":"product.live"}"#;
+        assert_ne!(sign("whsec_test", 1, body), sign("whsec_test", 2, body));
+    }
+
+    #[test]
+    fn is_public_ip_rejects_internal_v4_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(is_public_ip("8.8.8.8".parse().unwrap()));
+        assert!(is_public_ip("100.128.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn is_public_ip_rejects_internal_v6_ranges() {
+        for ip in [
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "2001:db8::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(is_public_ip("2606:4700:4700::1111".parse().unwrap()));
+        assert!(is_public_ip("::ffff:8.8.8.8".parse().unwrap()));
+    }
+}
@@ -9,6 +9,7 @@ import NotFound from "./pages/NotFound";
 import ProductForm from "./components/ProductForm";
 import VendorProducts from "./components/VendorProducts";
 import VendorNotifications from "./pages/VendorNotifications";
+import Login from "./pages/Login";
 
 const queryClient = new QueryClient();
 
@@ -20,6 +21,7 @@ const App = () => (
       <Router>
         <Routes>
           <Route path="/" element={<Index />} />
+          <Route path="/login" element={<Login />} />
           <Route path="/product-preview" element={<ProductPreview />} />
           <Route path="/products" element={<VendorProducts />} />
           <Route path="/products/:productId/edit" element={<ProductForm />} />
@@ -23,6 +23,7 @@ import { Tabs, TabsContent, TabsList, TabsTrigger } from "./ui/tabs";
 import { Check, Image, Plus, Save } from "lucide-react";
 import { Badge } from "./ui/badge";
 import { useParams } from "react-router-dom";
+import { authHeaders } from "../lib/session";
 
 interface Category {
   id: string;
@@ -98,13 +99,12 @@ const ProductForm: React.FC = () => {
   useEffect(() => {
     if (!productId) return;
     setIsProductLoading(true);
-    fetch(`http://localhost:8080/api/products`)
+    fetch(`http://localhost:8080/api/products/${productId}`, { headers: authHeaders() })
       .then(res => {
         if (!res.ok) throw new Error('Failed to fetch product');
         return res.json();
       })
-      .then(products => {
-        const product = products.find((p: { id: string }) => p.id === productId);
+      .then(product => {
         if (product) {
           setFormData(prev => ({
             ...prev,
@@ -112,7 +112,7 @@ const ProductForm: React.FC = () => {
             description: product.description || "",
             price: product.price ? String(product.price) : "",
             stockQuantity: product.stockQuantity ? String(product.stockQuantity) : "",
-            category: product.category_id || product.category || "",
+            category: product.category?.id || "",
             selectedTags: product.tags ? product.tags.map((t: { id: string }) => t.id) : [],
             images: [],
             imagePreviewUrls: product.image_url ? [product.image_url.startsWith('http') ? product.image_url : `http://localhost:8080${product.image_url}`] : [],
@@ -165,6 +165,7 @@ const ProductForm: React.FC = () => {
     try {
       const uploadRes = await fetch('http://localhost:8080/api/upload', {
         method: 'POST',
+        headers: authHeaders(),
         body: formDataUpload,
       });
       if (!uploadRes.ok) throw new Error('Failed to upload image');
@@ -239,12 +240,12 @@ const ProductForm: React.FC = () => {
     try {
       // Prepare the product data
       const productData = {
-        vendor_id: "00000000-0000-0000-0000-000000000000", // This should be the actual vendor ID from your auth system
         name: formData.name,
         description: formData.description,
-        price: parseFloat(formData.price),
+        // Sent as a string so the backend parses the exact decimal
+        price: formData.price.trim(),
+        currency: "XAF",
         image_url: formData.imagePreviewUrls[0] || "", // Using the first image as the main image
-        is_draft: false,
         category_id: formData.category || null,
         tag_ids: formData.selectedTags.length > 0 ? formData.selectedTags : null
       };
@@ -254,6 +255,7 @@ const ProductForm: React.FC = () => {
         method: 'POST',
         headers: {
           'Content-Type': 'application/json',
+          ...authHeaders(),
         },
         body: JSON.stringify(productData),
       });
@@ -1,9 +1,29 @@
-import React from "react";
-import { Link } from "react-router-dom";
+import React, { useEffect, useState } from "react";
+import { Link, useNavigate } from "react-router-dom";
 import { User } from "lucide-react";
 import { Button } from "./ui/button";
+import { Badge } from "./ui/badge";
+import { authHeaders, getVendorId, setToken } from "../lib/session";
 
 const VendorNavbar: React.FC = () => {
+  const navigate = useNavigate();
+  const [unread, setUnread] = useState(0);
+  const signedIn = getVendorId() !== null;
+
+  useEffect(() => {
+    const vendorId = getVendorId();
+    if (!vendorId) return;
+    fetch(`http://localhost:8080/api/vendors/${vendorId}/notifications/unread-count`, {
+      headers: authHeaders(),
+    })
+      .then((res) => {
+        if (!res.ok) throw new Error("Failed to fetch unread count");
+        return res.json();
+      })
+      .then((data) => setUnread(data.unread ?? 0))
+      .catch(() => setUnread(0));
+  }, []);
+
   return (
     <nav className="bg-cm-green text-white px-4 py-3 shadow-md">
       <div className="container mx-auto flex justify-between items-center">
@@ -30,12 +50,32 @@ const VendorNavbar: React.FC = () => {
           <Link to="/notifications">
             <Button variant="ghost" className="text-white hover:text-cm-yellow">
               Notifications
+              {unread > 0 && (
+                <Badge className="ml-2 bg-cm-yellow text-cm-green">
+                  {unread > 99 ? "99+" : unread}
+                </Badge>
+              )}
             </Button>
           </Link>
-          <Button className="bg-white text-cm-green hover:bg-cm-sand">
-            <User size={18} className="mr-2" />
-            Account
-          </Button>
+          {signedIn ? (
+            <Button
+              className="bg-white text-cm-green hover:bg-cm-sand"
+              onClick={() => {
+                setToken(null);
+                navigate("/login");
+              }}
+            >
+              <User size={18} className="mr-2" />
+              Sign out
+            </Button>
+          ) : (
+            <Link to="/login">
+              <Button className="bg-white text-cm-green hover:bg-cm-sand">
+                <User size={18} className="mr-2" />
+                Sign in
+              </Button>
+            </Link>
+          )}
         </div>
       </div>
     </nav>
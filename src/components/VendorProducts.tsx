@@ -5,15 +5,17 @@ import { Button } from "./ui/button";
 import { Badge } from "./ui/badge";
 import { ArrowLeft, Edit, Trash2 } from "lucide-react";
 import { useToast } from "./ui/use-toast";
+import { authHeaders, getVendorId } from "../lib/session";
 
 interface Product {
   id: string;
   name: string;
   description: string;
-  price: number;
+  // Decimal string, e.g. "12500.00"
+  price: string;
+  currency: string;
   image_url: string;
-  is_draft: boolean;
-  is_verified: boolean;
+  status: 'draft' | 'submitted' | 'in_review' | 'live' | 'rejected' | 'archived' | 'suspended';
   created_at: string;
   category?: {
     id: string;
@@ -34,12 +36,20 @@ const VendorProducts: React.FC = () => {
   useEffect(() => {
     const fetchProducts = async () => {
       try {
-        const response = await fetch('http://localhost:8080/api/products');
+        const vendorId = getVendorId();
+        if (!vendorId) {
+          navigate('/login');
+          return;
+        }
+        // The vendor's own listing includes drafts and other unpublished products
+        const response = await fetch(`http://localhost:8080/api/vendors/${vendorId}/products`, {
+          headers: authHeaders(),
+        });
         if (!response.ok) {
           throw new Error('Failed to fetch products');
         }
         const data = await response.json();
-        setProducts(data);
+        setProducts(data.items);
       } catch (error) {
         console.error('Error fetching products:', error);
         toast({
@@ -53,7 +63,7 @@ const VendorProducts: React.FC = () => {
     };
 
     fetchProducts();
-  }, [toast]);
+  }, [navigate, toast]);
 
   const handleEdit = (productId: string) => {
     navigate(`/products/${productId}/edit`);
@@ -63,6 +73,7 @@ const VendorProducts: React.FC = () => {
     try {
       const response = await fetch(`http://localhost:8080/api/products/${productId}`, {
         method: 'DELETE',
+        headers: authHeaders(),
       });
 
       if (!response.ok) {
@@ -143,10 +154,10 @@ const VendorProducts: React.FC = () => {
                   />
                   <div className="absolute top-2 right-2">
                     <Badge
-                      variant={product.is_verified ? "default" : "secondary"}
-                      className={product.is_verified ? "bg-cm-green" : "bg-gray-500"}
+                      variant={product.status === 'live' ? "default" : "secondary"}
+                      className={product.status === 'live' ? "bg-cm-green" : "bg-gray-500"}
                     >
-                      {product.is_verified ? "Verified" : "Pending"}
+                      {product.status === 'live' ? "Live" : product.status.replace('_', ' ')}
                     </Badge>
                   </div>
                 </div>
@@ -159,9 +170,9 @@ const VendorProducts: React.FC = () => {
                 <CardContent>
                   <div className="flex justify-between items-center mb-4">
                     <span className="text-2xl font-bold text-cm-green">
-                      {product.price.toLocaleString()} FCFA
+                      {Number(product.price).toLocaleString()} {product.currency === 'XAF' ? 'FCFA' : product.currency}
                     </span>
-                    {product.is_draft && (
+                    {product.status === 'draft' && (
                       <Badge variant="outline">Draft</Badge>
                     )}
                   </div>
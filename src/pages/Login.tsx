@@ -0,0 +1,109 @@
+import React, { useState } from "react";
+import { useNavigate, useSearchParams } from "react-router-dom";
+import VendorNavbar from "@/components/VendorNavbar";
+import { Button } from "@/components/ui/button";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { useToast } from "@/components/ui/use-toast";
+import { setToken } from "../lib/session";
+
+const API_URL = "http://localhost:8080/api";
+
+// Signs a vendor in. Opened as /login?setup_token=... it instead lets a vendor created before
+// passwords existed choose one, using the token from the `password-setup` admin command.
+const Login: React.FC = () => {
+  const navigate = useNavigate();
+  const { toast } = useToast();
+  const [searchParams] = useSearchParams();
+  const setupToken = searchParams.get("setup_token");
+  const [email, setEmail] = useState("");
+  const [password, setPassword] = useState("");
+  const [isSubmitting, setIsSubmitting] = useState(false);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setIsSubmitting(true);
+    try {
+      const response = setupToken
+        ? await fetch(`${API_URL}/auth/password-setup`, {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({ token: setupToken, password }),
+          })
+        : await fetch(`${API_URL}/auth/login`, {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({ email, password }),
+          });
+      const data = await response.json();
+      if (!response.ok) {
+        throw new Error(data.message || "Failed to sign in");
+      }
+      setToken(data.token);
+      toast({
+        title: "Signed in",
+        description: `Welcome, ${data.vendor.name}.`,
+      });
+      navigate("/products");
+    } catch (error) {
+      toast({
+        title: setupToken ? "Could not set password" : "Could not sign in",
+        description: error instanceof Error ? error.message : "Please try again.",
+        variant: "destructive",
+      });
+    } finally {
+      setIsSubmitting(false);
+    }
+  };
+
+  return (
+    <div className="min-h-screen flex flex-col bg-gray-50 cm-pattern-bg">
+      <VendorNavbar />
+      <div className="container mx-auto px-4 py-8 flex justify-center">
+        <Card className="w-full max-w-md">
+          <CardHeader>
+            <CardTitle>{setupToken ? "Choose a password" : "Vendor sign in"}</CardTitle>
+          </CardHeader>
+          <CardContent>
+            <form onSubmit={handleSubmit} className="space-y-4">
+              {!setupToken && (
+                <div className="space-y-2">
+                  <Label htmlFor="email">Email</Label>
+                  <Input
+                    id="email"
+                    type="email"
+                    autoComplete="email"
+                    value={email}
+                    onChange={(e) => setEmail(e.target.value)}
+                    required
+                  />
+                </div>
+              )}
+              <div className="space-y-2">
+                <Label htmlFor="password">Password</Label>
+                <Input
+                  id="password"
+                  type="password"
+                  autoComplete={setupToken ? "new-password" : "current-password"}
+                  value={password}
+                  onChange={(e) => setPassword(e.target.value)}
+                  required
+                />
+              </div>
+              <Button
+                type="submit"
+                className="w-full bg-cm-green hover:bg-cm-forest"
+                disabled={isSubmitting}
+              >
+                {setupToken ? "Set password" : "Sign in"}
+              </Button>
+            </form>
+          </CardContent>
+        </Card>
+      </div>
+    </div>
+  );
+};
+
+export default Login;
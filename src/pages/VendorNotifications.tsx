@@ -1,6 +1,7 @@
 import React, { useEffect, useState } from "react";
 import { Card, CardContent, CardHeader, CardTitle } from "../components/ui/card";
 import { Badge } from "../components/ui/badge";
+import { authHeaders, getVendorId } from "../lib/session";
 
 interface Notification {
   id: string;
@@ -9,18 +10,71 @@ interface Notification {
   created_at: string;
 }
 
-const VENDOR_ID = "00000000-0000-0000-0000-000000000000"; // Replace with real vendor ID if available
+const API_URL = "http://localhost:8080/api";
+const RECONNECT_DELAY_MS = 3000;
 
 const VendorNotifications: React.FC = () => {
   const [notifications, setNotifications] = useState<Notification[]>([]);
   const [loading, setLoading] = useState(true);
 
   useEffect(() => {
-    fetch(`http://localhost:8080/api/vendors/${VENDOR_ID}/notifications`)
-      .then((res) => res.json())
-      .then((data) => setNotifications(data))
+    const vendorId = getVendorId();
+    if (!vendorId) {
+      setLoading(false);
+      return;
+    }
+    fetch(`${API_URL}/vendors/${vendorId}/notifications`, { headers: authHeaders() })
+      .then((res) => {
+        if (!res.ok) throw new Error("Failed to fetch notifications");
+        return res.json();
+      })
+      .then((data) => setNotifications(data.items))
       .catch(() => setNotifications([]))
       .finally(() => setLoading(false));
+
+    // New notifications arrive over SSE. EventSource cannot send the Authorization header, so each
+    // connection uses a short-lived stream token and resumes from the last event it saw.
+    let source: EventSource | null = null;
+    let retry: ReturnType<typeof setTimeout> | undefined;
+    let lastEventId = "";
+    let closed = false;
+
+    const connect = async () => {
+      try {
+        const res = await fetch(`${API_URL}/vendors/${vendorId}/notifications/stream-token`, {
+          method: "POST",
+          headers: authHeaders(),
+        });
+        if (!res.ok) throw new Error("Failed to get a stream token");
+        const { token } = await res.json();
+        if (closed) return;
+        const params = new URLSearchParams({ token });
+        if (lastEventId) params.set("last_event_id", lastEventId);
+        source = new EventSource(`${API_URL}/vendors/${vendorId}/notifications/stream?${params}`);
+        source.addEventListener("notification", (event) => {
+          const message = event as MessageEvent;
+          lastEventId = message.lastEventId;
+          const notification: Notification = JSON.parse(message.data);
+          setNotifications((current) =>
+            current.some((n) => n.id === notification.id) ? current : [notification, ...current]
+          );
+        });
+        // The token has expired by the time EventSource would retry, so reconnect with a new one
+        source.onerror = () => {
+          source?.close();
+          if (!closed) retry = setTimeout(connect, RECONNECT_DELAY_MS);
+        };
+      } catch {
+        if (!closed) retry = setTimeout(connect, RECONNECT_DELAY_MS);
+      }
+    };
+    connect();
+
+    return () => {
+      closed = true;
+      clearTimeout(retry);
+      source?.close();
+    };
   }, []);
 
   return (